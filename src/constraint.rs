@@ -0,0 +1,76 @@
+use core::fmt;
+
+/// a strictly increasing slice of file indices, like the generated constants, which the
+/// solvers need to find the files in every constraint
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Constraint<'a>(&'a [usize]);
+
+impl<'a> Constraint<'a> {
+    /// checks that `values` is strictly increasing, which can be done at compile time
+    ///
+    /// ```ignore
+    /// const GRASS: Constraint = match Constraint::new(stem_words::_grass) {
+    ///     Ok(constraint) => constraint,
+    ///     Err(_) => panic!("not sorted"),
+    /// };
+    /// ```
+    pub const fn new(values: &'a [usize]) -> Result<Self, UnsortedError> {
+        let mut index = 1;
+        while index < values.len() {
+            if values[index - 1] >= values[index] {
+                return Err(UnsortedError {
+                    constraint: 0,
+                    index,
+                });
+            }
+            index += 1;
+        }
+        Ok(Self(values))
+    }
+
+    /// `values` must be strictly increasing, which the generated constants are, this is not
+    /// checked, and solving with a constraint that is not gives wrong files but never panics
+    pub const fn from_sorted(values: &'a [usize]) -> Self {
+        Self(values)
+    }
+
+    pub const fn as_slice(&self) -> &'a [usize] {
+        self.0
+    }
+}
+
+impl AsRef<[usize]> for Constraint<'_> {
+    fn as_ref(&self) -> &[usize] {
+        self.0
+    }
+}
+
+impl<'a> TryFrom<&'a [usize]> for Constraint<'a> {
+    type Error = UnsortedError;
+
+    fn try_from(values: &'a [usize]) -> Result<Self, Self::Error> {
+        Self::new(values)
+    }
+}
+
+/// a constraint is not strictly increasing, it is unsorted or has duplicates
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct UnsortedError {
+    /// the index of the constraint in the constraints passed to `Solver::try_solve`, `0` for
+    /// `Constraint::new`
+    pub constraint: usize,
+    /// the index in the constraint of the first value that is not greater than the one before it
+    pub index: usize,
+}
+
+impl fmt::Display for UnsortedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "constraint {} is not strictly increasing at index {}",
+            self.constraint, self.index
+        )
+    }
+}
+
+impl core::error::Error for UnsortedError {}
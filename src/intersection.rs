@@ -0,0 +1,142 @@
+use crate::constraint::Constraint;
+use core::{iter::FusedIterator, marker::PhantomData};
+
+/// the values in every constraint, found lazily without allocating by walking all the
+/// constraints at once, so `next`, `nth` and `take` only do as much work as they need
+///
+/// `constraints` can be an array, a mutable slice or anything else that can be borrowed as a
+/// mutable slice of constraints, the constraints are shortened as values are found
+///
+/// every value satisfies every constraint, like `Strictness::Strict`, and there are no values
+/// when there are no constraints
+///
+/// ```ignore
+/// let mut pngs = Intersection::new([stem_words::_grass, exts::_png].map(Constraint::from_sorted));
+/// let first = pngs.next();
+/// ```
+#[derive(Clone, Debug)]
+pub struct Intersection<'a, S> {
+    constraints: S,
+    lifetime: PhantomData<Constraint<'a>>,
+}
+
+impl<'a, S: AsMut<[Constraint<'a>]>> Intersection<'a, S> {
+    pub fn new(constraints: S) -> Self {
+        Self {
+            constraints,
+            lifetime: PhantomData,
+        }
+    }
+
+    /// the constraints with the values that have been found removed
+    pub fn into_inner(self) -> S {
+        self.constraints
+    }
+}
+
+/// the index of the first value in `values` that is at least `x`, found by galloping
+fn gallop(values: &[usize], x: usize) -> usize {
+    if values.first().is_none_or(|&first| first >= x) {
+        return 0;
+    }
+    let mut bound = 1;
+    while bound < values.len() && values[bound] < x {
+        bound *= 2;
+    }
+    // `values[bound / 2]` is less than `x`, and `values[bound]` is not, if it exists
+    let start = bound / 2;
+    let end = (bound + 1).min(values.len());
+    start + values[start..end].partition_point(|&value| value < x)
+}
+
+impl<'a, S: AsMut<[Constraint<'a>]>> Iterator for Intersection<'a, S> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let constraints = self.constraints.as_mut();
+        let mut candidate = *constraints.first()?.as_slice().first()?;
+        // every constraint is moved to the first value that is at least the candidate, which
+        // becomes the candidate, until every constraint starts with it
+        'search: loop {
+            for constraint in constraints.iter_mut() {
+                let values = constraint.as_slice();
+                let values = &values[gallop(values, candidate)..];
+                *constraint = Constraint::from_sorted(values);
+                let &value = values.first()?;
+                if value > candidate {
+                    candidate = value;
+                    continue 'search;
+                }
+            }
+            break;
+        }
+        for constraint in constraints.iter_mut() {
+            *constraint = Constraint::from_sorted(&constraint.as_slice()[1..]);
+        }
+        Some(candidate)
+    }
+}
+
+impl<'a, S: AsMut<[Constraint<'a>]>> FusedIterator for Intersection<'a, S> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn intersection<'a, const N: usize>(
+        constraints: [&'a [usize]; N],
+    ) -> Intersection<'a, [Constraint<'a>; N]> {
+        Intersection::new(constraints.map(Constraint::from_sorted))
+    }
+
+    #[test]
+    fn no_constraints() {
+        let mut values = intersection([]);
+        assert_eq!(values.next(), None);
+        assert_eq!(values.next(), None);
+    }
+
+    #[test]
+    fn empty_constraint() {
+        assert_eq!(intersection([&[]]).next(), None);
+        assert_eq!(intersection([&[1, 2, 3], &[], &[2, 3]]).next(), None);
+    }
+
+    #[test]
+    fn disjoint_constraints() {
+        assert_eq!(intersection([&[1, 3, 5], &[0, 2, 4, 6]]).next(), None);
+        assert_eq!(intersection([&[1, 2, 3], &[2, 3], &[1, 4]]).next(), None);
+    }
+
+    #[test]
+    fn values_in_every_constraint() {
+        assert!(
+            intersection([
+                &[0, 1, 2, 3, 5, 8, 13],
+                &[1, 3, 5, 7, 9, 11, 13],
+                &[1, 5, 6, 13]
+            ])
+            .eq([1, 5, 13])
+        );
+        assert!(intersection([&[2, 4, 8]]).eq([2, 4, 8]));
+    }
+
+    #[test]
+    fn stops_early() {
+        let constraints: [&[usize]; 2] = [&[0, 2, 4, 6, 8, 10], &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]];
+        let mut values = intersection(constraints);
+        assert_eq!(values.nth(1), Some(2));
+        // only the values up to the one found are removed
+        assert_eq!(
+            values.into_inner().map(|constraint| constraint.as_slice()),
+            [&[4, 6, 8, 10][..], &[3, 4, 5, 6, 7, 8, 9]],
+        );
+
+        let mut values = intersection(constraints);
+        assert!(values.by_ref().take(3).eq([0, 2, 4]));
+        assert_eq!(
+            values.into_inner().map(|constraint| constraint.as_slice()),
+            [&[6, 8, 10][..], &[5, 6, 7, 8, 9]],
+        );
+    }
+}
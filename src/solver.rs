@@ -0,0 +1,687 @@
+use crate::constraint::Constraint;
+#[cfg(feature = "alloc")]
+use crate::constraint::UnsortedError;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::fmt;
+
+/// how constraints that cannot be satisfied are handled
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Strictness {
+    /// every value satisfies every constraint, so there may be no values
+    Strict,
+    /// a constraint is skipped if it cannot be satisfied given the previous constraints
+    Nonstrict,
+}
+
+/// what a [`Solver`] finds
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Solution<T> {
+    /// there are no constraints, so every file satisfies them
+    Unconstrained,
+    /// no file satisfies the constraints
+    Empty,
+    /// the output for the files that satisfy the constraints
+    Matches(T),
+}
+
+/// what a [`Solver`] outputs for the values that satisfy the constraints
+#[cfg(feature = "alloc")]
+pub trait Output {
+    type Value;
+
+    /// `values` is sorted and not empty
+    fn output(values: Vec<usize>) -> Self::Value;
+}
+
+/// every value, in order
+#[derive(Clone, Copy, Debug, Default)]
+pub struct All;
+
+/// the smallest value
+#[derive(Clone, Copy, Debug, Default)]
+pub struct First;
+
+/// the number of values
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Count;
+
+/// nothing, for when only whether there are any values matters
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Exists;
+
+#[cfg(feature = "alloc")]
+impl Output for All {
+    type Value = Vec<usize>;
+
+    fn output(values: Vec<usize>) -> Self::Value {
+        values
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Output for First {
+    type Value = usize;
+
+    fn output(values: Vec<usize>) -> Self::Value {
+        values[0]
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Output for Count {
+    type Value = usize;
+
+    fn output(values: Vec<usize>) -> Self::Value {
+        values.len()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Output for Exists {
+    type Value = ();
+
+    fn output(_: Vec<usize>) -> Self::Value {}
+}
+
+/// finds the values in every constraint, where a constraint is a sorted slice of file indices
+/// like the generated constants
+///
+/// ```ignore
+/// let solver = Solver::new(Strictness::Nonstrict, First);
+/// let constraints = [stem_words::_grass, exts::_png].map(Constraint::from_sorted);
+/// match solver.solve(constraints.into_iter()) {
+///     Solution::Unconstrained => {}
+///     Solution::Empty => {}
+///     Solution::Matches(file) => {}
+/// }
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Solver<O> {
+    pub strictness: Strictness,
+    pub output: O,
+}
+
+impl<O> Solver<O> {
+    pub const fn new(strictness: Strictness, output: O) -> Self {
+        Self { strictness, output }
+    }
+
+    /// writes the values to the start of `buffer` instead of outputting them, and returns how
+    /// many there are, which never allocates
+    ///
+    /// `buffer` must hold every value of the first constraint that is used, which is the first
+    /// one, or for `Strictness::Nonstrict` the first one that is not empty
+    pub fn solve_into<'a>(
+        &self,
+        constraints: impl Iterator<Item = Constraint<'a>>,
+        buffer: &mut [usize],
+    ) -> Result<Solution<usize>, BufferTooSmall> {
+        solve_in(self.strictness, constraints, buffer)
+    }
+
+    /// writes the values to `scratch`, and returns how many there are, which only allocates
+    /// when `scratch` has never held as many values
+    #[cfg(feature = "alloc")]
+    pub fn solve_with<'a>(
+        &self,
+        constraints: impl Iterator<Item = Constraint<'a>>,
+        scratch: &mut Scratch,
+    ) -> Solution<usize> {
+        let Ok(solution) = solve_in(self.strictness, constraints, &mut scratch.values);
+        scratch.len = match solution {
+            Solution::Matches(len) => len,
+            _ => 0,
+        };
+        solution
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<O: Output> Solver<O> {
+    /// never panics
+    pub fn solve<'a>(
+        &self,
+        constraints: impl Iterator<Item = Constraint<'a>>,
+    ) -> Solution<O::Value> {
+        self.solve_sorted(constraints)
+    }
+
+    /// like `solve`, but checks that every constraint is strictly increasing, including those
+    /// after the solution is settled, and returns the first that is not
+    pub fn try_solve(
+        &self,
+        constraints: impl Iterator<Item = impl AsRef<[usize]>>,
+    ) -> Result<Solution<O::Value>, UnsortedError> {
+        let mut constraints = constraints.enumerate();
+        let mut error = None;
+        let solution = self.solve_sorted(constraints.by_ref().map_while(|(constraint, values)| {
+            match check_sorted(constraint, values.as_ref()) {
+                Ok(()) => Some(values),
+                Err(err) => {
+                    error = Some(err);
+                    None
+                }
+            }
+        }));
+        if let Some(err) = error {
+            return Err(err);
+        }
+        // the solver stops once the solution cannot change, so the rest are checked here
+        for (constraint, values) in constraints {
+            check_sorted(constraint, values.as_ref())?;
+        }
+        Ok(solution)
+    }
+
+    /// `constraints` must be strictly increasing, otherwise the solution is wrong
+    pub(crate) fn solve_sorted(
+        &self,
+        constraints: impl Iterator<Item = impl AsRef<[usize]>>,
+    ) -> Solution<O::Value> {
+        let mut values = Vec::new();
+        let Ok(solution) = solve_in(self.strictness, constraints, &mut values);
+        match solution {
+            Solution::Unconstrained => Solution::Unconstrained,
+            Solution::Empty => Solution::Empty,
+            Solution::Matches(len) => {
+                values.truncate(len);
+                Solution::Matches(O::output(values))
+            }
+        }
+    }
+}
+
+/// checks that the constraint at index `constraint` is strictly increasing
+#[cfg(feature = "alloc")]
+fn check_sorted(constraint: usize, values: &[usize]) -> Result<(), UnsortedError> {
+    match Constraint::new(values) {
+        Ok(_) => Ok(()),
+        Err(err) => Err(UnsortedError { constraint, ..err }),
+    }
+}
+
+/// a reusable buffer for `Solver::solve_with`
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, Default)]
+pub struct Scratch {
+    values: Vec<usize>,
+    /// how many of `values` are the last solution
+    len: usize,
+}
+
+#[cfg(feature = "alloc")]
+impl Scratch {
+    pub const fn new() -> Self {
+        Self {
+            values: Vec::new(),
+            len: 0,
+        }
+    }
+
+    /// the values of the last solution, empty when it was not `Solution::Matches`
+    pub fn values(&self) -> &[usize] {
+        &self.values[..self.len]
+    }
+}
+
+/// the buffer passed to `Solver::solve_into` cannot hold the first constraint
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BufferTooSmall {
+    /// how many values the buffer needs to hold
+    pub needed: usize,
+}
+
+impl fmt::Display for BufferTooSmall {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "buffer is too small, it needs to hold {} values",
+            self.needed
+        )
+    }
+}
+
+impl core::error::Error for BufferTooSmall {}
+
+/// where the partial solution is kept while solving
+trait Buffer {
+    type Error;
+
+    /// replaces the start of the buffer with `values`
+    fn fill(&mut self, values: &[usize]) -> Result<(), Self::Error>;
+
+    fn values_mut(&mut self) -> &mut [usize];
+}
+
+impl Buffer for [usize] {
+    type Error = BufferTooSmall;
+
+    fn fill(&mut self, values: &[usize]) -> Result<(), Self::Error> {
+        let Some(start) = self.get_mut(..values.len()) else {
+            return Err(BufferTooSmall {
+                needed: values.len(),
+            });
+        };
+        start.copy_from_slice(values);
+        Ok(())
+    }
+
+    fn values_mut(&mut self) -> &mut [usize] {
+        self
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Buffer for Vec<usize> {
+    type Error = core::convert::Infallible;
+
+    fn fill(&mut self, values: &[usize]) -> Result<(), Self::Error> {
+        self.clear();
+        self.extend_from_slice(values);
+        Ok(())
+    }
+
+    fn values_mut(&mut self) -> &mut [usize] {
+        self
+    }
+}
+
+/// solves in `buffer`, returning how many values are at its start
+fn solve_in<B: Buffer + ?Sized>(
+    strictness: Strictness,
+    mut constraints: impl Iterator<Item = impl AsRef<[usize]>>,
+    buffer: &mut B,
+) -> Result<Solution<usize>, B::Error> {
+    let Some(first_constraint) = constraints.next() else {
+        return Ok(Solution::Unconstrained);
+    };
+    let first_constraint = match strictness {
+        Strictness::Strict => first_constraint,
+        Strictness::Nonstrict => {
+            if first_constraint.as_ref().is_empty() {
+                if let Some(possible_files) = constraints
+                    .find(|token_associated_files| !token_associated_files.as_ref().is_empty())
+                {
+                    possible_files
+                } else {
+                    return Ok(Solution::Empty);
+                }
+            } else {
+                first_constraint
+            }
+        }
+    };
+    buffer.fill(first_constraint.as_ref())?;
+    let mut len = first_constraint.as_ref().len();
+    for constraint in constraints {
+        // the constraint cannot change the partial solution
+        let settled = match strictness {
+            Strictness::Strict => len == 0,
+            Strictness::Nonstrict => len == 1,
+        };
+        if settled {
+            break;
+        }
+        // nothing is written when the intersection is empty, so the nonstrict solver can keep
+        // the partial solution
+        let next_len = intersect(&mut buffer.values_mut()[..len], constraint.as_ref());
+        if strictness == Strictness::Strict || next_len > 0 {
+            len = next_len;
+        }
+    }
+    Ok(if len == 0 {
+        Solution::Empty
+    } else {
+        Solution::Matches(len)
+    })
+}
+
+/// moves the values of `partial_solution` that are also in `constraint`, which are both
+/// sorted, to its start, and returns how many there are
+fn intersect(partial_solution: &mut [usize], constraint: &[usize]) -> usize {
+    let (
+        Some(&partial_first),
+        Some(&partial_last),
+        Some(&constraint_first),
+        Some(&constraint_last),
+    ) = (
+        partial_solution.first(),
+        partial_solution.last(),
+        constraint.first(),
+        constraint.last(),
+    )
+    else {
+        return 0;
+    };
+    if partial_last < constraint_first || partial_first > constraint_last {
+        return 0;
+    }
+    let mut i = 0;
+    let mut j = 0;
+    // gallops to where the slices start to overlap
+    if partial_first > constraint_first {
+        let x = partial_first;
+        let mut b = constraint.len() / 2;
+        while b > 0 {
+            while j + b < constraint.len() && constraint[j + b] <= x {
+                j += b;
+            }
+            b /= 2;
+        }
+    } else {
+        let x = constraint_first;
+        let mut b = partial_solution.len() / 2;
+        while b > 0 {
+            while i + b < partial_solution.len() && partial_solution[i + b] <= x {
+                i += b;
+            }
+            b /= 2;
+        }
+    }
+    // `len` never passes `i`, so the values are moved before they are read
+    let mut len = 0;
+    while i < partial_solution.len() && j < constraint.len() {
+        if partial_solution[i] == constraint[j] {
+            partial_solution[len] = partial_solution[i];
+            len += 1;
+            i += 1;
+            j += 1;
+        } else if partial_solution[i] < constraint[j] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    len
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::*;
+    use alloc::{string::ToString, vec};
+
+    /// xorshift, so the cases are the same on every run
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, bound: u64) -> usize {
+            (self.next() % bound) as usize
+        }
+
+        /// up to 5 constraints of values below 24, often empty or disjoint
+        fn constraints(&mut self) -> Vec<Vec<usize>> {
+            (0..self.below(6))
+                .map(|_| {
+                    let density = self.next() % 4;
+                    (0..24)
+                        .filter(|_| density > 0 && self.next() % 4 < density)
+                        .collect()
+                })
+                .collect()
+        }
+    }
+
+    /// filters the values of the first constraint one constraint at a time, `None` for no
+    /// constraints
+    fn reference(strictness: Strictness, constraints: &[Vec<usize>]) -> Option<Vec<usize>> {
+        let mut constraints = constraints.iter();
+        let mut values = match strictness {
+            Strictness::Strict => constraints.next()?.clone(),
+            Strictness::Nonstrict => {
+                if constraints.len() == 0 {
+                    return None;
+                }
+                match constraints.find(|constraint| !constraint.is_empty()) {
+                    Some(constraint) => constraint.clone(),
+                    None => return Some(Vec::new()),
+                }
+            }
+        };
+        for constraint in constraints {
+            let next_values: Vec<usize> = values
+                .iter()
+                .copied()
+                .filter(|value| constraint.contains(value))
+                .collect();
+            if strictness == Strictness::Strict || !next_values.is_empty() {
+                values = next_values;
+            }
+        }
+        Some(values)
+    }
+
+    /// the solution for what `reference` returns
+    fn solution<T>(
+        expected: &Option<Vec<usize>>,
+        output: impl Fn(&Vec<usize>) -> T,
+    ) -> Solution<T> {
+        match expected {
+            None => Solution::Unconstrained,
+            Some(values) if values.is_empty() => Solution::Empty,
+            Some(values) => Solution::Matches(output(values)),
+        }
+    }
+
+    fn solve<O: Output>(
+        strictness: Strictness,
+        output: O,
+        constraints: &[Vec<usize>],
+    ) -> Solution<O::Value> {
+        Solver::new(strictness, output)
+            .solve(constraints.iter().map(|c| Constraint::from_sorted(c)))
+    }
+
+    #[test]
+    fn matches_reference() {
+        let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+        let mut scratch = Scratch::new();
+        let mut buffer = [0; 24];
+        for _ in 0..20_000 {
+            let constraints = rng.constraints();
+            let constraint_iter = || constraints.iter().map(|c| Constraint::from_sorted(c));
+            for strictness in [Strictness::Strict, Strictness::Nonstrict] {
+                let expected = reference(strictness, &constraints);
+                let case = (strictness, &constraints);
+
+                assert_eq!(
+                    solve(strictness, All, &constraints),
+                    solution(&expected, Vec::clone),
+                    "{case:?}",
+                );
+                assert_eq!(
+                    solve(strictness, First, &constraints),
+                    solution(&expected, |values| values[0]),
+                    "{case:?}",
+                );
+                assert_eq!(
+                    solve(strictness, Count, &constraints),
+                    solution(&expected, Vec::len),
+                    "{case:?}",
+                );
+                assert_eq!(
+                    solve(strictness, Exists, &constraints),
+                    solution(&expected, |_| ()),
+                    "{case:?}",
+                );
+
+                let solver = Solver::new(strictness, All);
+                assert_eq!(
+                    solver.solve_with(constraint_iter(), &mut scratch),
+                    solution(&expected, Vec::len),
+                    "{case:?}",
+                );
+                assert_eq!(scratch.values(), expected.as_deref().unwrap_or(&[]));
+                let solution_into = solver.solve_into(constraint_iter(), &mut buffer);
+                assert_eq!(solution_into, Ok(solution(&expected, Vec::len)), "{case:?}");
+                if let Ok(Solution::Matches(len)) = solution_into {
+                    assert_eq!(Some(&buffer[..len]), expected.as_deref());
+                }
+
+                let first = match &expected {
+                    None => Some(0),
+                    Some(values) => values.first().copied(),
+                };
+                match strictness {
+                    Strictness::Strict => {
+                        assert_eq!(
+                            crate::solve_constraints_strict(constraints.iter()),
+                            expected
+                        );
+                        assert_eq!(crate::first_value_strict(constraints.iter()), first);
+                    }
+                    Strictness::Nonstrict => {
+                        assert_eq!(
+                            crate::solve_constraints_nonstrict(constraints.iter()),
+                            expected,
+                        );
+                        assert_eq!(crate::first_value_nonstrict(constraints.iter()), first);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn unconstrained_and_empty() {
+        let none: [&[usize]; 0] = [];
+        for strictness in [Strictness::Strict, Strictness::Nonstrict] {
+            assert_eq!(
+                Solver::new(strictness, All).solve_sorted(none.iter()),
+                Solution::Unconstrained,
+            );
+            assert_eq!(
+                Solver::new(strictness, Count).solve_sorted([&[][..], &[]].iter()),
+                Solution::Empty,
+            );
+        }
+        assert_eq!(crate::solve_constraints_strict(none.iter()), None);
+        assert_eq!(crate::solve_constraints_nonstrict(none.iter()), None);
+        assert_eq!(crate::first_value_strict(none.iter()), Some(0));
+        assert_eq!(crate::first_value_nonstrict(none.iter()), Some(0));
+        let empty_first: [&[usize]; 2] = [&[], &[1, 2]];
+        assert_eq!(
+            crate::solve_constraints_strict(empty_first.iter()),
+            Some(Vec::new())
+        );
+        assert_eq!(
+            crate::solve_constraints_nonstrict(empty_first.iter()),
+            Some(vec![1, 2])
+        );
+        assert_eq!(crate::first_value_strict(empty_first.iter()), None);
+        assert_eq!(crate::first_value_nonstrict(empty_first.iter()), Some(1));
+    }
+
+    #[test]
+    fn buffer_too_small() {
+        let constraints: [&[usize]; 2] = [&[1, 2, 3, 4], &[2, 4]];
+        let mut buffer = [0; 3];
+        for strictness in [Strictness::Strict, Strictness::Nonstrict] {
+            assert_eq!(
+                Solver::new(strictness, All).solve_into(
+                    constraints.map(Constraint::from_sorted).into_iter(),
+                    &mut buffer,
+                ),
+                Err(BufferTooSmall { needed: 4 }),
+            );
+        }
+    }
+
+    #[test]
+    fn nonstrict_buffer_fits_first_nonempty_constraint() {
+        let constraints: [&[usize]; 4] = [&[], &[3, 5], &[], &[0, 1, 2, 3, 4, 5, 6]];
+        let mut buffer = [0; 2];
+        assert_eq!(
+            Solver::new(Strictness::Nonstrict, All).solve_into(
+                constraints.map(Constraint::from_sorted).into_iter(),
+                &mut buffer,
+            ),
+            Ok(Solution::Matches(2)),
+        );
+        assert_eq!(buffer, [3, 5]);
+        // the strict solver stops at the empty constraint, so it needs no buffer at all
+        assert_eq!(
+            Solver::new(Strictness::Strict, All).solve_into(
+                constraints.map(Constraint::from_sorted).into_iter(),
+                &mut [],
+            ),
+            Ok(Solution::Empty),
+        );
+    }
+
+    #[test]
+    fn scratch_is_reused() {
+        let solver = Solver::new(Strictness::Strict, All);
+        let mut scratch = Scratch::new();
+        let long: [&[usize]; 1] = [&[0, 1, 2, 3, 4, 5, 6, 7]];
+        let short: [&[usize]; 2] = [&[1, 2, 3], &[2, 3]];
+        let disjoint: [&[usize]; 2] = [&[1], &[2]];
+        assert_eq!(
+            solver.solve_with(long.map(Constraint::from_sorted).into_iter(), &mut scratch),
+            Solution::Matches(8),
+        );
+        let capacity = scratch.values.capacity();
+        assert_eq!(
+            solver.solve_with(short.map(Constraint::from_sorted).into_iter(), &mut scratch),
+            Solution::Matches(2),
+        );
+        assert_eq!(scratch.values(), [2, 3]);
+        assert_eq!(scratch.values.capacity(), capacity);
+        assert_eq!(
+            solver.solve_with(
+                disjoint.map(Constraint::from_sorted).into_iter(),
+                &mut scratch
+            ),
+            Solution::Empty,
+        );
+        assert_eq!(scratch.values(), []);
+    }
+
+    #[test]
+    fn try_solve_checks_every_constraint() {
+        let try_solve = |strictness, constraints: &[&[usize]]| {
+            Solver::new(strictness, All).try_solve(constraints.iter())
+        };
+        assert_eq!(
+            try_solve(Strictness::Strict, &[&[1, 2], &[2, 3]]),
+            Ok(Solution::Matches(vec![2])),
+        );
+        assert_eq!(
+            try_solve(Strictness::Strict, &[&[1, 2], &[2, 2]]),
+            Err(UnsortedError {
+                constraint: 1,
+                index: 1,
+            }),
+        );
+        // the solution is settled before the unsorted constraints
+        assert_eq!(
+            try_solve(Strictness::Strict, &[&[1], &[2], &[3], &[5, 4]]),
+            Err(UnsortedError {
+                constraint: 3,
+                index: 1,
+            }),
+        );
+        assert_eq!(
+            try_solve(Strictness::Nonstrict, &[&[1], &[1, 3, 2]]),
+            Err(UnsortedError {
+                constraint: 1,
+                index: 2,
+            }),
+        );
+        assert_eq!(
+            UnsortedError {
+                constraint: 1,
+                index: 2,
+            }
+            .to_string(),
+            "constraint 1 is not strictly increasing at index 2",
+        );
+    }
+}
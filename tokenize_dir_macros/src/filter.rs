@@ -0,0 +1,83 @@
+use crate::traverse::Traversal;
+use anyhow::{Context, Result};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use ignore::WalkBuilder;
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+use syn::LitStr;
+
+/// decides which paths under a root are tokenized
+///
+/// patterns are matched against paths relative to the root, `*` does not match `/`,
+/// so `**/*.png` matches png files at any depth
+#[derive(Default)]
+pub struct Filter {
+    /// `None` includes every file
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+}
+
+fn glob_set(patterns: &[LitStr]) -> syn::Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = GlobBuilder::new(&pattern.value())
+            .literal_separator(true)
+            .build()
+            .map_err(|err| syn::Error::new(pattern.span(), err))?;
+        builder.add(glob);
+    }
+    builder
+        .build()
+        .map_err(|err| syn::Error::new(patterns[0].span(), err))
+}
+
+impl Filter {
+    /// an empty `include` includes every file
+    pub fn new(include: &[LitStr], exclude: &[LitStr]) -> syn::Result<Self> {
+        Ok(Self {
+            include: if include.is_empty() {
+                None
+            } else {
+                Some(glob_set(include)?)
+            },
+            exclude: glob_set(exclude)?,
+        })
+    }
+
+    /// whether to descend into the directory at `relative`
+    pub fn dir(&self, relative: &Path) -> bool {
+        !self.exclude.is_match(relative)
+    }
+
+    /// whether to tokenize the file at `relative`
+    pub fn file(&self, relative: &Path) -> bool {
+        !self.exclude.is_match(relative)
+            && self
+                .include
+                .as_ref()
+                .is_none_or(|include| include.is_match(relative))
+    }
+}
+
+/// every path under `root` that is not ignored by a `.gitignore`, `.ignore`, git exclude or
+/// global git ignore file, with the same rules as ripgrep, walked like `traversal` walks it
+pub fn not_ignored(root: &Path, traversal: &Traversal) -> Result<HashSet<PathBuf>> {
+    let mut paths = HashSet::new();
+    let walk = WalkBuilder::new(root)
+        .standard_filters(false)
+        .ignore(true)
+        .git_ignore(true)
+        .git_global(true)
+        .git_exclude(true)
+        .parents(true)
+        .follow_links(traversal.follow_links)
+        .max_depth(traversal.max_depth.map(|max_depth| max_depth + 1))
+        .build();
+    for entry in walk {
+        let entry = entry.with_context(|| format!("failed to walk `{}`", root.display()))?;
+        paths.insert(entry.into_path());
+    }
+    Ok(paths)
+}
@@ -0,0 +1,298 @@
+use crate::root::Root;
+use proc_macro2::Span;
+use std::collections::{BTreeMap, HashSet};
+use syn::{
+    Ident, LitStr,
+    parse::{Parse, ParseStream},
+};
+use unicode_ident::{is_xid_continue, is_xid_start};
+
+/// what happens when different names generate the same identifier in a module
+#[derive(Clone, Copy, Default)]
+pub enum Collisions {
+    /// `error`, a compile error lists the names
+    #[default]
+    Error,
+    /// `suffix`, the names are sorted, the first keeps the identifier and the others get
+    /// `__2`, `__3`, ... appended, skipping identifiers that are already taken
+    Suffix,
+}
+
+impl Parse for Collisions {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let ident = input.parse::<Ident>()?;
+        match ident.to_string().as_str() {
+            "error" => Ok(Self::Error),
+            "suffix" => Ok(Self::Suffix),
+            _ => Err(syn::Error::new(
+                ident.span(),
+                format!("unknown collision handling `{ident}`, expected `error` or `suffix`"),
+            )),
+        }
+    }
+}
+
+/// keywords that cannot be raw identifiers
+const NOT_RAW: &[&str] = &["_", "crate", "self", "Self", "super"];
+
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let",
+    "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return",
+    "static", "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use",
+    "virtual", "where", "while", "yield",
+];
+
+/// how names are turned into identifiers
+pub struct Mangling {
+    /// prepended to every identifier
+    pub prefix: String,
+    /// whether names are transliterated to ASCII first
+    pub ascii: bool,
+}
+
+impl Default for Mangling {
+    fn default() -> Self {
+        Self {
+            prefix: "_".to_owned(),
+            ascii: false,
+        }
+    }
+}
+
+impl Mangling {
+    /// `prefix` must only contain chars that can continue an identifier
+    pub fn new(prefix: Option<&LitStr>, ascii: bool) -> syn::Result<Self> {
+        let mut mangling = Self {
+            ascii,
+            ..Default::default()
+        };
+        if let Some(prefix) = prefix {
+            mangling.prefix = prefix.value();
+            if !mangling.prefix.chars().all(is_xid_continue) {
+                return Err(syn::Error::new(
+                    prefix.span(),
+                    "prefix must only contain chars that can be in an identifier",
+                ));
+            }
+        }
+        Ok(mangling)
+    }
+
+    /// the identifier for `name` followed by `suffix`, as a string that starts with `r#` for
+    /// raw identifiers
+    ///
+    /// 1. with `ascii`, `name` is transliterated to ASCII, `Straße` is `Strasse`
+    /// 2. every char that cannot be in an identifier is replaced with `_`
+    /// 3. `prefix` is prepended and `suffix` appended
+    /// 4. `_` is prepended if that cannot start an identifier, `01_intro` is `_01_intro`
+    /// 5. keywords become raw identifiers, `type` is `r#type`, except for those that cannot be
+    ///    raw and the names in `reserved`, which get `_` appended, `self` is `self_`
+    pub fn ident(&self, name: &str, suffix: &str, reserved: &[&str]) -> String {
+        let name = if self.ascii {
+            deunicode::deunicode(name)
+        } else {
+            name.to_owned()
+        };
+        let mut ident = self.prefix.clone();
+        ident.extend(
+            name.chars()
+                .map(|c| if is_xid_continue(c) { c } else { '_' }),
+        );
+        ident.push_str(suffix);
+        if !ident.starts_with(|c| c == '_' || is_xid_start(c)) {
+            ident.insert(0, '_');
+        }
+        if NOT_RAW.contains(&ident.as_str()) || reserved.contains(&ident.as_str()) {
+            ident.push('_');
+        } else if KEYWORDS.contains(&ident.as_str()) {
+            ident.insert_str(0, "r#");
+        }
+        ident
+    }
+}
+
+/// something an identifier is generated for
+pub struct Name {
+    /// what the identifier was generated from, for errors
+    pub source: String,
+    /// from `Mangling::ident`
+    pub ident: String,
+    /// the index of the root the name comes from
+    pub root: usize,
+}
+
+/// an identifier for every name in a module, in the same order
+pub fn unique_idents(
+    names: &[Name],
+    collisions: Collisions,
+    roots: &[Root],
+) -> syn::Result<Vec<Ident>> {
+    let mut by_ident = BTreeMap::<&str, Vec<usize>>::new();
+    for (i, name) in names.iter().enumerate() {
+        by_ident.entry(&name.ident).or_default().push(i);
+    }
+    let mut taken = by_ident
+        .keys()
+        .map(|ident| ident.to_string())
+        .collect::<HashSet<_>>();
+    let mut idents = names
+        .iter()
+        .map(|name| name.ident.clone())
+        .collect::<Vec<_>>();
+    let mut errors = Vec::new();
+    for (ident, mut group) in by_ident {
+        if group.len() == 1 {
+            continue;
+        }
+        group.sort_by(|&a, &b| names[a].source.cmp(&names[b].source));
+        match collisions {
+            Collisions::Error => {
+                let mut sources = group
+                    .iter()
+                    .map(|&i| format!("`{}`", names[i].source))
+                    .collect::<Vec<_>>();
+                let last = sources.pop().unwrap_or_default();
+                let quantifier = if sources.len() == 1 { "both" } else { "all" };
+                let message = format!(
+                    "{} and {last} {quantifier} generate the identifier `{ident}`, rename them or add `collisions = suffix`",
+                    sources.join(", ")
+                );
+                let mut reported = Vec::new();
+                for &i in &group {
+                    if !reported.contains(&names[i].root) {
+                        reported.push(names[i].root);
+                        errors.push(syn::Error::new(roots[names[i].root].span, &message));
+                    }
+                }
+            }
+            Collisions::Suffix => {
+                let mut suffix = 2;
+                for &i in &group[1..] {
+                    while taken.contains(&format!("{ident}__{suffix}")) {
+                        suffix += 1;
+                    }
+                    idents[i] = format!("{ident}__{suffix}");
+                    taken.insert(idents[i].clone());
+                }
+            }
+        }
+    }
+    if let Some(mut error) = errors.pop() {
+        for other in errors {
+            error.combine(other);
+        }
+        return Err(error);
+    }
+    Ok(idents
+        .into_iter()
+        .map(|ident| match ident.strip_prefix("r#") {
+            Some(raw) => Ident::new_raw(raw, Span::call_site()),
+            None => Ident::new(&ident, Span::call_site()),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn root() -> Root {
+        Root {
+            base: None,
+            base_dir: PathBuf::new(),
+            path: PathBuf::from("/assets"),
+            written: PathBuf::from("/assets"),
+            alias: None,
+            span: Span::call_site(),
+        }
+    }
+
+    /// the identifiers of `sources` with the default mangling, by source
+    fn idents(sources: &[&str], collisions: Collisions) -> syn::Result<Vec<(String, String)>> {
+        let mangling = Mangling::default();
+        let names = sources
+            .iter()
+            .map(|source| Name {
+                source: source.to_string(),
+                ident: mangling.ident(source, "", &[]),
+                root: 0,
+            })
+            .collect::<Vec<_>>();
+        let idents = unique_idents(&names, collisions, &[root()])?;
+        let mut idents = sources
+            .iter()
+            .map(|source| source.to_string())
+            .zip(idents.iter().map(Ident::to_string))
+            .collect::<Vec<_>>();
+        idents.sort();
+        Ok(idents)
+    }
+
+    fn pairs(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|&(source, ident)| (source.to_owned(), ident.to_owned()))
+            .collect()
+    }
+
+    #[test]
+    fn collision_error() {
+        let err = idents(&["a-b", "c", "a_b"], Collisions::Error).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "`a-b` and `a_b` both generate the identifier `_a_b`, rename them or add `collisions = suffix`"
+        );
+        let err = idents(&["a b", "a-b", "a_b"], Collisions::Error).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "`a b`, `a-b` and `a_b` all generate the identifier `_a_b`, rename them or add `collisions = suffix`"
+        );
+    }
+
+    #[test]
+    fn suffix_skips_taken_identifiers() {
+        assert_eq!(
+            idents(&["a_b", "a_b__2", "a-b"], Collisions::Suffix).unwrap(),
+            pairs(&[("a-b", "_a_b"), ("a_b", "_a_b__3"), ("a_b__2", "_a_b__2")])
+        );
+    }
+
+    #[test]
+    fn suffix_is_independent_of_order() {
+        let sources = ["a b", "a-b", "a_b", "c"];
+        let expected = pairs(&[
+            ("a b", "_a_b"),
+            ("a-b", "_a_b__2"),
+            ("a_b", "_a_b__3"),
+            ("c", "_c"),
+        ]);
+        assert_eq!(idents(&sources, Collisions::Suffix).unwrap(), expected);
+        let reversed = sources.into_iter().rev().collect::<Vec<_>>();
+        assert_eq!(idents(&reversed, Collisions::Suffix).unwrap(), expected);
+    }
+
+    #[test]
+    fn mangling() {
+        let unprefixed = Mangling {
+            prefix: String::new(),
+            ascii: false,
+        };
+        let ascii = Mangling {
+            prefix: String::new(),
+            ascii: true,
+        };
+        let reserved = crate::GENERATED_MODULES;
+        assert_eq!(Mangling::default().ident("grass", "", &[]), "_grass");
+        assert_eq!(unprefixed.ident("01_intro", "", &[]), "_01_intro");
+        assert_eq!(unprefixed.ident("type", "", &[]), "r#type");
+        assert_eq!(unprefixed.ident("self", "", &[]), "self_");
+        assert_eq!(unprefixed.ident("stem_words", "", reserved), "stem_words_");
+        assert_eq!(unprefixed.ident("stem_words", "", &[]), "stem_words");
+        assert_eq!(ascii.ident("Straße", "", &[]), "Strasse");
+        assert_eq!(unprefixed.ident("Straße", "", &[]), "Straße");
+        assert_eq!(unprefixed.ident("big rock.v2", "", &[]), "big_rock_v2");
+        assert_eq!(unprefixed.ident("a", "__2", &[]), "a__2");
+    }
+}
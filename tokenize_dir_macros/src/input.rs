@@ -0,0 +1,341 @@
+use crate::{
+    ident::Collisions,
+    order::Order,
+    root::Overlay,
+    split::{Case, Delimiter},
+};
+use proc_macro2::Span;
+use syn::{
+    Ident, LitBool, LitInt, LitStr, Token, Visibility, bracketed,
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+};
+
+/// a root directory, optionally followed by the identifier of its module, `"dir" as name`
+pub struct RootLit {
+    pub lit: LitStr,
+    /// `None` when the identifier is generated from the directory
+    pub alias: Option<Ident>,
+}
+
+impl Parse for RootLit {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let lit = input.parse()?;
+        let alias = if input.peek(Token![as]) {
+            input.parse::<Token![as]>()?;
+            Some(input.parse()?)
+        } else {
+            None
+        };
+        Ok(Self { lit, alias })
+    }
+}
+
+/// the input of `tokenize_dir!`, either
+/// - `"dir", ..., key = value, ...`, where the roots can also be given as an option
+/// - `"dir", ...; "delim", ...` optionally followed by `; key = value, ...`
+///
+/// where every root can be followed by `as name`, and the options are
+/// - `roots = ["dir", "dir" as name, ...]`
+/// - `delimiters = ["delim", regex(r"[\s_]+"), whitespace, ...]`, defaults to `_`, `-` and
+///   space
+/// - `overlay = first | last`, which root a file is kept from when several roots have a file
+///   at the same relative path, by default every file is kept
+/// - `include = ["glob", ...]` and `exclude = ["glob", ...]`
+/// - `case = preserve | lowercase | fold`, defaults to `preserve`
+/// - `gitignore = bool`, defaults to `false`
+/// - `max_depth = 2`, of the directories entered below the roots, defaults to no limit
+/// - `follow_links = bool`, defaults to `true`
+/// - `hidden = bool`, whether files and directories starting with `.` are tokenized, defaults
+///   to `true`
+/// - `dir_words = bool`, whether the directories of files are tokens, defaults to `false`
+/// - `merged = bool`, whether token modules for every root are generated at the top level,
+///   defaults to `false`
+/// - `visibility = pub(crate)`, of the generated top level items, defaults to `pub`
+/// - `collisions = error | suffix`, defaults to `error`
+/// - `prefix = "_"`, of every generated identifier, defaults to `_`
+/// - `ascii = bool`, whether identifiers are transliterated to ASCII, defaults to `false`
+/// - `order = likelihood | path | natural | modified | size`, of the file indices, defaults to
+///   `likelihood`
+/// - `lockfile = "path"`, where the index of every file is recorded, resolved like the roots
+pub struct Input {
+    pub roots: Vec<RootLit>,
+    /// `None` when roots are not overlaid
+    pub overlay: Option<Overlay>,
+    /// `None` when no delimiters are given
+    pub delimiters: Option<Vec<Delimiter>>,
+    pub case: Case,
+    pub include: Vec<LitStr>,
+    pub exclude: Vec<LitStr>,
+    pub gitignore: bool,
+    /// `None` when every directory is entered
+    pub max_depth: Option<usize>,
+    pub follow_links: bool,
+    pub hidden: bool,
+    pub dir_words: bool,
+    pub merged: bool,
+    pub visibility: Visibility,
+    pub collisions: Collisions,
+    /// `None` when no prefix is given
+    pub prefix: Option<LitStr>,
+    pub ascii: bool,
+    pub order: Order,
+    /// `None` when indices are not locked
+    pub lockfile: Option<LitStr>,
+}
+
+/// sets an option, which can only be given once
+fn set_once<T>(option: &mut Option<T>, key: &Ident, value: T) -> syn::Result<()> {
+    if option.is_some() {
+        return Err(syn::Error::new(
+            key.span(),
+            format!("`{key}` is given more than once"),
+        ));
+    }
+    *option = Some(value);
+    Ok(())
+}
+
+/// `item, ...` up to the first item that does not start with a string literal, returns whether
+/// there was a trailing comma
+fn parse_lits<T: Parse>(input: ParseStream, lits: &mut Vec<T>) -> syn::Result<bool> {
+    while input.peek(LitStr) {
+        lits.push(input.parse()?);
+        if !input.peek(Token![,]) {
+            return Ok(false);
+        }
+        input.parse::<Token![,]>()?;
+    }
+    Ok(true)
+}
+
+/// `[item, ...]`
+fn parse_list<T: Parse>(input: ParseStream) -> syn::Result<Vec<T>> {
+    let content;
+    bracketed!(content in input);
+    Ok(Punctuated::<T, Token![,]>::parse_terminated(&content)?
+        .into_iter()
+        .collect())
+}
+
+const OPTIONS: &[&str] = &[
+    "roots",
+    "overlay",
+    "delimiters",
+    "case",
+    "include",
+    "exclude",
+    "gitignore",
+    "max_depth",
+    "follow_links",
+    "hidden",
+    "dir_words",
+    "merged",
+    "visibility",
+    "collisions",
+    "prefix",
+    "ascii",
+    "order",
+    "lockfile",
+];
+
+impl Parse for Input {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut positional_roots = Vec::<RootLit>::new();
+        let mut roots = None;
+        let mut overlay = None;
+        let mut delimiters = None;
+        let mut case = None;
+        let mut include = None;
+        let mut exclude = None;
+        let mut gitignore = None;
+        let mut max_depth = None;
+        let mut follow_links = None;
+        let mut hidden = None;
+        let mut dir_words = None;
+        let mut merged = None;
+        let mut visibility = None;
+        let mut collisions = None;
+        let mut prefix = None;
+        let mut ascii = None;
+        let mut order = None;
+        let mut lockfile = None;
+        let mut separated = parse_lits(input, &mut positional_roots)?;
+        if !positional_roots.is_empty() && input.peek(Token![;]) {
+            input.parse::<Token![;]>()?;
+            let mut lits = Vec::new();
+            parse_lits(input, &mut lits)?;
+            delimiters = Some(lits.into_iter().map(Delimiter::Literal).collect());
+            separated = input.is_empty() || input.parse::<Token![;]>().is_ok();
+        }
+        while !input.is_empty() {
+            if !separated {
+                return Err(input.error("expected `,`"));
+            }
+            let key = input.parse::<Ident>()?;
+            input.parse::<Token![=]>()?;
+            match key.to_string().as_str() {
+                "roots" => set_once(&mut roots, &key, parse_list(input)?)?,
+                "overlay" => set_once(&mut overlay, &key, input.parse()?)?,
+                "delimiters" => set_once(&mut delimiters, &key, parse_list(input)?)?,
+                "case" => set_once(&mut case, &key, input.parse()?)?,
+                "include" => set_once(&mut include, &key, parse_list(input)?)?,
+                "exclude" => set_once(&mut exclude, &key, parse_list(input)?)?,
+                "gitignore" => set_once(&mut gitignore, &key, input.parse::<LitBool>()?.value)?,
+                "max_depth" => set_once(
+                    &mut max_depth,
+                    &key,
+                    input.parse::<LitInt>()?.base10_parse()?,
+                )?,
+                "follow_links" => {
+                    set_once(&mut follow_links, &key, input.parse::<LitBool>()?.value)?
+                }
+                "hidden" => set_once(&mut hidden, &key, input.parse::<LitBool>()?.value)?,
+                "dir_words" => set_once(&mut dir_words, &key, input.parse::<LitBool>()?.value)?,
+                "merged" => set_once(&mut merged, &key, input.parse::<LitBool>()?.value)?,
+                "visibility" => set_once(&mut visibility, &key, input.parse()?)?,
+                "collisions" => set_once(&mut collisions, &key, input.parse()?)?,
+                "prefix" => set_once(&mut prefix, &key, input.parse()?)?,
+                "ascii" => set_once(&mut ascii, &key, input.parse::<LitBool>()?.value)?,
+                "order" => set_once(&mut order, &key, input.parse()?)?,
+                "lockfile" => set_once(&mut lockfile, &key, input.parse()?)?,
+                _ => {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        format!(
+                            "unknown option `{key}`, expected one of {}",
+                            OPTIONS
+                                .iter()
+                                .map(|option| format!("`{option}`"))
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        ),
+                    ));
+                }
+            }
+            separated = input.is_empty() || input.parse::<Token![,]>().is_ok();
+        }
+        let roots = match roots {
+            Some(roots) if positional_roots.is_empty() => roots,
+            Some(_) => {
+                return Err(syn::Error::new(
+                    positional_roots[0].lit.span(),
+                    "roots are given both before the options and with `roots`",
+                ));
+            }
+            None => positional_roots,
+        };
+        if roots.is_empty() {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                "expected at least one directory",
+            ));
+        }
+        Ok(Self {
+            roots,
+            overlay,
+            delimiters,
+            case: case.unwrap_or_default(),
+            include: include.unwrap_or_default(),
+            exclude: exclude.unwrap_or_default(),
+            gitignore: gitignore.unwrap_or_default(),
+            max_depth,
+            follow_links: follow_links.unwrap_or(true),
+            hidden: hidden.unwrap_or(true),
+            dir_words: dir_words.unwrap_or_default(),
+            merged: merged.unwrap_or_default(),
+            visibility: visibility.unwrap_or_else(|| Visibility::Public(Default::default())),
+            collisions: collisions.unwrap_or_default(),
+            prefix,
+            ascii: ascii.unwrap_or_default(),
+            order: order.unwrap_or_default(),
+            lockfile,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> Input {
+        match syn::parse_str::<Input>(input) {
+            Ok(input) => input,
+            Err(err) => panic!("`{input}` is rejected: {err}"),
+        }
+    }
+
+    fn error(input: &str) -> String {
+        match syn::parse_str::<Input>(input) {
+            Ok(_) => panic!("`{input}` is accepted"),
+            Err(err) => err.to_string(),
+        }
+    }
+
+    fn roots(input: &Input) -> Vec<String> {
+        input.roots.iter().map(|root| root.lit.value()).collect()
+    }
+
+    fn literal_delimiters(input: &Input) -> Option<Vec<String>> {
+        input.delimiters.as_ref().map(|delimiters| {
+            delimiters
+                .iter()
+                .map(|delimiter| match delimiter {
+                    Delimiter::Literal(lit) => lit.value(),
+                    _ => panic!("not a literal"),
+                })
+                .collect()
+        })
+    }
+
+    #[test]
+    fn old_form() {
+        let input = parse(r#""d"; "_""#);
+        assert_eq!(roots(&input), ["d"]);
+        assert_eq!(literal_delimiters(&input), Some(vec!["_".to_owned()]));
+
+        let input = parse(r#""d", "e"; "_", "-"; exclude = ["*.txt"], hidden = false"#);
+        assert_eq!(roots(&input), ["d", "e"]);
+        assert_eq!(
+            literal_delimiters(&input),
+            Some(vec!["_".to_owned(), "-".to_owned()])
+        );
+        assert_eq!(input.exclude[0].value(), "*.txt");
+        assert!(!input.hidden);
+
+        let input = parse(r#""d";"#);
+        assert_eq!(literal_delimiters(&input), Some(Vec::new()));
+    }
+
+    #[test]
+    fn options() {
+        let input = parse(r#""d", max_depth = 1"#);
+        assert_eq!(roots(&input), ["d"]);
+        assert_eq!(literal_delimiters(&input), None);
+        assert_eq!(input.max_depth, Some(1));
+        assert!(input.follow_links);
+
+        let input = parse(r#"roots = ["d", "e" as e], gitignore = true,"#);
+        assert_eq!(roots(&input), ["d", "e"]);
+        assert_eq!(input.roots[1].alias.as_ref().unwrap(), "e");
+        assert!(input.gitignore);
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(
+            error(r#""d", roots = ["e"]"#),
+            "roots are given both before the options and with `roots`"
+        );
+        assert_eq!(
+            error(r#""d", hidden = true, hidden = false"#),
+            "`hidden` is given more than once"
+        );
+        assert!(
+            error(r#""d", colour = true"#)
+                .starts_with("unknown option `colour`, expected one of `roots`")
+        );
+        assert_eq!(error(r#""d", hidden = true max_depth = 1"#), "expected `,`");
+        assert_eq!(error("hidden = true"), "expected at least one directory");
+    }
+}
@@ -1,69 +1,107 @@
-use anyhow::{Result, anyhow};
+#![cfg_attr(feature = "nightly", feature(proc_macro_tracked_path))]
+
+use anyhow::{Context, Result, anyhow};
+use filter::{Filter, not_ignored};
+use ident::{Collisions, Mangling, Name, unique_idents};
+use input::Input;
+use lock::Lockfile;
+use order::{Order, natural_cmp};
 use proc_macro2::{Literal, Span, TokenTree};
-use quote::{format_ident, quote};
-use regex::Regex;
+use quote::{format_ident, quote, quote_spanned};
+use root::{Overlay, Root};
+use split::{Case, Splitter};
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     fs,
-    path::{Path, PathBuf},
-    str::FromStr,
+    path::{Component, Path, PathBuf},
 };
-use syn::{LitStr, Token, parse::Parse, punctuated::Punctuated};
+use syn::{Ident, Visibility, ext::IdentExt, parse_quote};
+use traverse::{DirId, Traversal, dir_id};
 
-struct Input {
-    dir_paths: Punctuated<LitStr, Token![,]>,
-    _comma: Token![;],
-    delimiters: Punctuated<LitStr, Token![,]>,
-}
-
-impl Parse for Input {
-    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
-        Ok(Self {
-            dir_paths: Punctuated::parse_separated_nonempty(input)?,
-            _comma: input.parse()?,
-            delimiters: Punctuated::parse_terminated(input)?,
-        })
-    }
-}
+mod filter;
+mod ident;
+mod input;
+mod lock;
+mod order;
+mod root;
+mod split;
+mod traverse;
 
 #[proc_macro]
 pub fn tokenize_dir(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = syn::parse_macro_input!(input as Input);
-    let dir_paths = input
-        .dir_paths
-        .into_iter()
-        .map(|lit| lit.value())
-        .collect::<Vec<_>>();
-    let dir_paths = dir_paths
-        .into_iter()
-        .map(|dir_path| PathBuf::from_str(&dir_path).unwrap());
-    let delimiters = input.delimiters.iter().map(|lit| lit.value());
-    let delimiters = Regex::new(
-        &delimiters
-            .map(|d| regex::escape(&d))
-            .collect::<Vec<_>>()
-            .join("|"),
-    )
-    .unwrap();
-    tokenize_dir_inner(&dir_paths.collect::<Vec<_>>(), &delimiters)
-        .unwrap()
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
         .into()
 }
 
+/// the options that apply to every root
+struct Options {
+    splitter: Splitter,
+    case: Case,
+    collisions: Collisions,
+    mangling: Mangling,
+    order: Order,
+    /// `None` when roots are not overlaid
+    overlay: Option<Overlay>,
+    /// `None` when indices are not locked
+    lockfile: Option<Lockfile>,
+    filter: Filter,
+    gitignore: bool,
+    traversal: Traversal,
+    dir_words: bool,
+    merged: bool,
+    visibility: Visibility,
+}
+
+fn expand(input: Input) -> syn::Result<proc_macro2::TokenStream> {
+    let roots = input
+        .roots
+        .iter()
+        .map(|root| {
+            Ok(Root {
+                alias: root.alias.clone(),
+                ..Root::parse(&root.lit)?
+            })
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+    let options = Options {
+        splitter: Splitter::new(input.delimiters.as_deref())?,
+        case: input.case,
+        collisions: input.collisions,
+        mangling: Mangling::new(input.prefix.as_ref(), input.ascii)?,
+        order: input.order,
+        overlay: input.overlay,
+        lockfile: input.lockfile.as_ref().map(Lockfile::parse).transpose()?,
+        filter: Filter::new(&input.include, &input.exclude)?,
+        gitignore: input.gitignore,
+        traversal: Traversal {
+            max_depth: input.max_depth,
+            follow_links: input.follow_links,
+            hidden: input.hidden,
+        },
+        dir_words: input.dir_words,
+        merged: input.merged,
+        visibility: input.visibility,
+    };
+    tokenize_dir_inner(&roots, &options)
+}
+
 #[derive(Debug, Default, Clone)]
 struct File {
     // should be unique
     path: PathBuf,
-    stem_word_tokens: HashSet<(String, usize)>,
-    ext_tokens: HashSet<(String, usize)>,
+    stem_word_tokens: BTreeSet<(String, usize)>,
+    ext_tokens: BTreeSet<(String, usize)>,
+    dir_word_tokens: BTreeSet<(String, usize)>,
     num_files_in_dir: usize,
 }
 
 impl File {
     fn negative_log_likelihood(
         &self,
-        all_stem_word_tokens: &HashMap<(String, usize), HashSet<PathBuf>>,
-        all_ext_tokens: &HashMap<(String, usize), HashSet<PathBuf>>,
+        all_stem_word_tokens: &BTreeMap<(String, usize), BTreeSet<PathBuf>>,
+        all_ext_tokens: &BTreeMap<(String, usize), BTreeSet<PathBuf>>,
         num_files: usize,
     ) -> usize {
         let mut l = 0;
@@ -84,64 +122,146 @@ impl File {
 #[derive(Debug)]
 struct Directory {
     files: Vec<File>,
+    path: PathBuf,
     name: String,
     sub_dirs: Vec<Directory>,
-    stem_word_tokens: HashMap<(String, usize), HashSet<PathBuf>>,
-    ext_tokens: HashMap<(String, usize), HashSet<PathBuf>>,
+    stem_word_tokens: BTreeMap<(String, usize), BTreeSet<PathBuf>>,
+    ext_tokens: BTreeMap<(String, usize), BTreeSet<PathBuf>>,
+    dir_word_tokens: BTreeMap<(String, usize), BTreeSet<PathBuf>>,
 }
 
-fn tokenize_dir_inner_inner<P: AsRef<Path>>(path: P, delimiters: &Regex) -> Result<Directory> {
+/// the last component of `path` as a `&str`
+fn file_name_str(path: &Path) -> Result<&str> {
+    path.file_name()
+        .ok_or_else(|| anyhow!("`{}` has no file name", path.display()))?
+        .to_str()
+        .ok_or_else(|| anyhow!("`{}` is not valid UTF-8", path.display()))
+}
+
+/// what is shared by every directory visited while walking a root
+struct Walk<'a> {
+    /// the directory the walk started in, which the filter matches relative to
+    root: &'a Path,
+    options: &'a Options,
+    /// the paths under `root` that are not ignored, `None` when ignore files are not honored
+    not_ignored: Option<HashSet<PathBuf>>,
+    /// the paths relative to their roots of the files in roots that override this one
+    overridden: &'a HashSet<PathBuf>,
+    /// the directories being walked, from the root down, to detect symlinks to an ancestor
+    ancestors: Vec<(DirId, PathBuf)>,
+    /// every kind of token and normalized token, with the tokens that were normalized to it,
+    /// tokens of different kinds are in different modules, so they are not merged
+    normalized: BTreeMap<(&'static str, String), BTreeSet<String>>,
+}
+
+impl Walk<'_> {
+    /// normalizes the case of `token` of `kind`, remembering the original
+    fn normalize(&mut self, kind: &'static str, token: &str) -> String {
+        let normalized = self.options.case.normalize(token).into_owned();
+        if self.options.case != Case::Preserve {
+            self.normalized
+                .entry((kind, normalized.clone()))
+                .or_default()
+                .insert(token.to_owned());
+        }
+        normalized
+    }
+}
+
+fn tokenize_dir_inner_inner<P: AsRef<Path>>(path: P, walk: &mut Walk) -> Result<Directory> {
+    let path = path.as_ref();
     let mut files = Vec::new();
     let mut children = Vec::new();
-    let dir = path
-        .as_ref()
-        .file_name()
-        .ok_or(anyhow!(""))?
-        .to_str()
-        .ok_or(anyhow!("to_str failed"))?
-        .to_owned();
+    let dir = file_name_str(path)?.to_owned();
     let mut num_files_in_dir = 0;
-    for dir_entry in fs::read_dir(path).unwrap() {
-        let path = dir_entry?.path();
+    let id = dir_id(path)?;
+    if let Some((_, ancestor)) = walk.ancestors.iter().find(|(ancestor, _)| *ancestor == id) {
+        return Err(anyhow!(
+            "`{}` links to `{}`, which contains it, add `follow_links = false` or exclude it",
+            path.display(),
+            ancestor.display()
+        ));
+    }
+    walk.ancestors.push((id, path.to_owned()));
+    let mut paths = Vec::new();
+    let read_dir = fs::read_dir(path)
+        .with_context(|| format!("failed to read directory `{}`", path.display()))?;
+    for dir_entry in read_dir {
+        let dir_entry = dir_entry
+            .with_context(|| format!("failed to read an entry of `{}`", path.display()))?;
+        paths.push(dir_entry.path());
+    }
+    // the order of entries is unspecified, so they are sorted to make the output reproducible
+    paths.sort();
+    for path in paths {
+        if walk
+            .not_ignored
+            .as_ref()
+            .is_some_and(|not_ignored| !not_ignored.contains(&path))
+        {
+            continue;
+        }
+        let relative = path.strip_prefix(walk.root)?;
+        if !walk.options.traversal.entry(&path, relative)? {
+            continue;
+        }
         if path.is_file() {
+            if !walk.options.filter.file(relative) || walk.overridden.contains(relative) {
+                continue;
+            }
             files.push(File {
                 path,
                 ..Default::default()
             });
             num_files_in_dir += 1;
         } else if path.is_dir() {
-            let foo = tokenize_dir_inner_inner(path, delimiters)?;
-            children.push(foo);
+            if !walk.options.filter.dir(relative) || !walk.options.traversal.dir(relative) {
+                continue;
+            }
+            let child = tokenize_dir_inner_inner(&path, walk)?;
+            children.push(child);
         }
     }
+    walk.ancestors.pop();
     for file in &mut files {
         file.num_files_in_dir = num_files_in_dir;
     }
     let mut file_names = Vec::new();
     for file in &files {
-        file_names.push(
-            file.path
-                .file_name()
-                .ok_or(anyhow!("file_name failed"))?
+        file_names.push(file_name_str(&file.path)?.to_owned());
+    }
+    let mut stem_word_tokens: BTreeMap<(String, usize), BTreeSet<PathBuf>> = BTreeMap::new();
+    let mut ext_tokens: BTreeMap<(String, usize), BTreeSet<PathBuf>> = BTreeMap::new();
+    let mut dir_word_tokens: BTreeMap<(String, usize), BTreeSet<PathBuf>> = BTreeMap::new();
+    // the directories between the root and the files, which are the same for every file here
+    let mut dir_word_counts = BTreeMap::new();
+    if walk.options.dir_words {
+        for component in path.strip_prefix(walk.root)?.components() {
+            let component = component
+                .as_os_str()
                 .to_str()
-                .ok_or(anyhow!("to_str failed"))?
-                .to_owned(),
-        );
+                .ok_or_else(|| anyhow!("`{}` is not valid UTF-8", path.display()))?;
+            *dir_word_counts
+                .entry(walk.normalize("directory", component))
+                .or_insert(0usize) += 1;
+        }
     }
-    let mut stem_word_tokens: HashMap<(String, usize), HashSet<PathBuf>> = HashMap::new();
-    let mut ext_tokens: HashMap<(String, usize), HashSet<PathBuf>> = HashMap::new();
-    for (file, file_name) in files.iter_mut().zip(file_names.into_iter()) {
+    for (file, file_name) in files.iter_mut().zip(file_names) {
         let mut stem = file_name.clone();
-        let mut ext_counts = HashMap::new();
+        let mut ext_counts = BTreeMap::new();
         if let Some((new_stem, exts)) = file_name.split_once(".") {
             stem = new_stem.to_owned();
             for etx in exts.split(".") {
-                *ext_counts.entry(etx.to_owned()).or_insert(0usize) += 1;
+                *ext_counts
+                    .entry(walk.normalize("extension", etx))
+                    .or_insert(0usize) += 1;
             }
         }
-        let mut stem_word_counts = HashMap::new();
-        for word in delimiters.split(&stem).filter(|part| !part.is_empty()) {
-            *stem_word_counts.entry(word.to_owned()).or_insert(0usize) += 1;
+        let mut stem_word_counts = BTreeMap::new();
+        for word in walk.options.splitter.split(&stem) {
+            *stem_word_counts
+                .entry(walk.normalize("stem word", word))
+                .or_insert(0usize) += 1;
         }
         for (word, &count) in &stem_word_counts {
             for version in 0..count {
@@ -161,6 +281,15 @@ fn tokenize_dir_inner_inner<P: AsRef<Path>>(path: P, delimiters: &Regex) -> Resu
                 file.ext_tokens.insert((word.to_owned(), version));
             }
         }
+        for (word, &count) in &dir_word_counts {
+            for version in 0..count {
+                dir_word_tokens
+                    .entry((word.to_owned(), version))
+                    .or_default()
+                    .insert(file.path.clone());
+                file.dir_word_tokens.insert((word.to_owned(), version));
+            }
+        }
     }
     if !children.is_empty() {
         // let mut stem_word_tokens_multiple: HashMap<(String, usize), HashSet<PathBuf>> =
@@ -226,6 +355,14 @@ fn tokenize_dir_inner_inner<P: AsRef<Path>>(path: P, delimiters: &Regex) -> Resu
                 }
             }
         }
+        for child in &children {
+            for (dir_word_token, paths) in &child.dir_word_tokens {
+                let full_paths = dir_word_tokens.entry(dir_word_token.clone()).or_default();
+                for path in paths {
+                    full_paths.insert(path.clone());
+                }
+            }
+        }
     }
     for child in &mut children {
         for file in &child.files {
@@ -234,17 +371,33 @@ fn tokenize_dir_inner_inner<P: AsRef<Path>>(path: P, delimiters: &Regex) -> Resu
     }
     Ok(Directory {
         files,
+        path: path.to_owned(),
         name: dir,
         sub_dirs: children,
         stem_word_tokens,
         ext_tokens,
+        dir_word_tokens,
     })
 }
 
+/// what is shared by every module generated for the roots
+struct Generate<'a> {
+    roots: &'a [Root],
+    options: &'a Options,
+    file_to_index: &'a HashMap<PathBuf, usize>,
+}
+
+/// the modules generated in every directory module, which sub directories cannot be named
+const GENERATED_MODULES: &[&str] = &["stem_words", "exts", "dir_words"];
+
+/// `kind` is what the tokens are, for docs, and `root` the root they come from, `None` when
+/// they can come from any root
 fn create_const_arrays(
-    tokens: &HashMap<(String, usize), HashSet<PathBuf>>,
-    file_to_index: &HashMap<PathBuf, usize>,
-) -> impl Iterator<Item = proc_macro2::TokenStream> {
+    tokens: &BTreeMap<(String, usize), BTreeSet<PathBuf>>,
+    kind: &str,
+    root: Option<usize>,
+    generate: &Generate,
+) -> syn::Result<Vec<proc_macro2::TokenStream>> {
     let mut max_is = HashMap::new();
     for (word, i) in tokens.keys() {
         max_is
@@ -252,108 +405,514 @@ fn create_const_arrays(
             .and_modify(|x: &mut usize| *x = (*x).max(*i))
             .or_insert(*i);
     }
-    tokens.iter().map(move |((word, i), files)| {
-        let word = if max_is[word] == 0 {
-            let word = word
-                .chars()
-                .map(|c| if c.is_alphanumeric() { c } else { '_' })
-                .collect::<String>();
-            format_ident!("_{}", word)
+    let mangling = &generate.options.mangling;
+    let mut names = Vec::new();
+    let mut docs = Vec::new();
+    for ((word, i), files) in tokens {
+        let root = root.unwrap_or_else(|| {
+            files
+                .first()
+                .and_then(|file| {
+                    generate
+                        .roots
+                        .iter()
+                        .position(|root| file.starts_with(&root.path))
+                })
+                .unwrap_or_default()
+        });
+        if max_is[word] == 0 {
+            names.push(Name {
+                source: word.clone(),
+                ident: mangling.ident(word, "", &[]),
+                root,
+            });
+            docs.push(format!("the files with the {kind} `{word}`"));
         } else {
-            let word = word
-                .chars()
-                .map(|c| if c.is_alphanumeric() { c } else { '_' })
-                .collect::<String>();
-            format_ident!("_{}_{}", word, i)
+            names.push(Name {
+                source: format!("{word} ({})", i + 1),
+                ident: mangling.ident(word, &format!("_{i}"), &[]),
+                root,
+            });
+            docs.push(format!(
+                "the files with the {kind} `{word}` at least {} times",
+                i + 1
+            ));
+        }
+    }
+    let idents = unique_idents(&names, generate.options.collisions, generate.roots)?;
+    Ok(tokens
+        .values()
+        .zip(idents)
+        .zip(docs)
+        .map(|((files, word), doc)| {
+            let mut file_indices = files
+                .iter()
+                .map(|file| generate.file_to_index[file])
+                .collect::<Vec<_>>();
+            file_indices.sort();
+            let file_indices = file_indices
+                .into_iter()
+                .map(|file_index| TokenTree::Literal(Literal::usize_unsuffixed(file_index)));
+            quote! {
+                #[doc = #doc]
+                pub const #word: &[usize] = &[ #(#file_indices,)* ];
+            }
+        })
+        .collect())
+}
+
+/// the identifiers of the modules generated for `directories`
+fn dir_idents(
+    directories: &[Directory],
+    root: usize,
+    generate: &Generate,
+) -> syn::Result<Vec<Ident>> {
+    let names = directories
+        .iter()
+        .map(|directory| Name {
+            source: directory.path.display().to_string(),
+            ident: generate
+                .options
+                .mangling
+                .ident(&directory.name, "", GENERATED_MODULES),
+            root,
+        })
+        .collect::<Vec<_>>();
+    unique_idents(&names, generate.options.collisions, generate.roots)
+}
+
+/// the identifiers of the modules generated for the roots, the alias of a root, or else its
+/// directory name preceded by as many parent directories as tell it apart from the other roots,
+/// roots that cannot be told apart get suffixes like with `collisions = suffix`
+fn root_idents(generate: &Generate) -> syn::Result<Vec<Ident>> {
+    let roots = generate.roots;
+    let mut aliases = Vec::new();
+    for root in roots {
+        let Some(alias) = &root.alias else {
+            continue;
         };
-        let mut file_indices = files
+        let unraw = alias.unraw().to_string();
+        if aliases.contains(&unraw) {
+            return Err(syn::Error::new(
+                alias.span(),
+                format!("`{alias}` is the alias of more than one root"),
+            ));
+        }
+        if GENERATED_MODULES.contains(&unraw.as_str()) {
+            return Err(syn::Error::new(
+                alias.span(),
+                format!("`{alias}` is the name of a generated module"),
+            ));
+        }
+        aliases.push(unraw);
+    }
+    let mut reserved = GENERATED_MODULES.to_vec();
+    reserved.extend(aliases.iter().map(String::as_str));
+    // the names of the directories of every root as it is written, from the root up, or the
+    // name of its base for roots like `$OUT_DIR`
+    let components = roots
+        .iter()
+        .map(|root| {
+            let components = root
+                .written
+                .components()
+                .rev()
+                .filter_map(|component| match component {
+                    Component::Normal(name) => Some(name.to_string_lossy()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            if components.is_empty() {
+                vec![root.base.unwrap_or("root").to_lowercase().into()]
+            } else {
+                components
+            }
+        })
+        .collect::<Vec<_>>();
+    let mut depths = components
+        .iter()
+        .map(|components| components.len().min(1))
+        .collect::<Vec<_>>();
+    let names = loop {
+        let names = roots
             .iter()
-            .map(|file| file_to_index[file])
+            .enumerate()
+            .map(|(i, root)| {
+                let source = components[i][..depths[i]]
+                    .iter()
+                    .rev()
+                    .map(|name| name.as_ref())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                let ident = match &root.alias {
+                    Some(alias) => alias.to_string(),
+                    None => generate.options.mangling.ident(&source, "", &reserved),
+                };
+                Name {
+                    source,
+                    ident,
+                    root: i,
+                }
+            })
             .collect::<Vec<_>>();
-        file_indices.sort();
-        let file_indices = file_indices
-            .into_iter()
-            .map(|file_index| TokenTree::Literal(Literal::usize_unsuffixed(file_index)));
-        quote! {
-            pub const #word: &[usize] = &[ #(#file_indices,)* ];
+        // only the directories up to the first one that differs are added, so names stay short
+        let mut grew = false;
+        for (i, name) in names.iter().enumerate() {
+            if roots[i].alias.is_some() {
+                continue;
+            }
+            for (j, other) in names.iter().enumerate() {
+                if i == j || other.ident != name.ident {
+                    continue;
+                }
+                // a root inside a directory named like the other root, like `a/assets` next to
+                // `assets`, differs by the directory after the other root
+                let differs = components[i]
+                    .iter()
+                    .zip(&components[j])
+                    .position(|(a, b)| a != b)
+                    .or((components[i].len() > components[j].len()).then_some(components[j].len()));
+                if let Some(differs) = differs
+                    && depths[i] <= differs
+                {
+                    depths[i] = differs + 1;
+                    grew = true;
+                }
+            }
+        }
+        if !grew {
+            break names;
+        }
+    };
+    unique_idents(&names, Collisions::Suffix, roots)
+}
+
+/// the `stem_words`, `exts` and, with the `dir_words` option, `dir_words` modules for
+/// `tokens` in that order
+fn token_modules(
+    stem_word_tokens: &BTreeMap<(String, usize), BTreeSet<PathBuf>>,
+    ext_tokens: &BTreeMap<(String, usize), BTreeSet<PathBuf>>,
+    dir_word_tokens: &BTreeMap<(String, usize), BTreeSet<PathBuf>>,
+    root: Option<usize>,
+    visibility: &Visibility,
+    generate: &Generate,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let stem_word_tokens = create_const_arrays(stem_word_tokens, "stem word", root, generate)?;
+    let ext_tokens = create_const_arrays(ext_tokens, "extension", root, generate)?;
+    let dir_words = if generate.options.dir_words {
+        let dir_word_tokens = create_const_arrays(dir_word_tokens, "directory", root, generate)?;
+        Some(quote! {
+            /// the files with each directory between the root and them
+            #visibility mod dir_words {
+                #(#dir_word_tokens)*
+            }
+        })
+    } else {
+        None
+    };
+    Ok(quote! {
+        /// the files with each word in their stem
+        #visibility mod stem_words {
+            #(#stem_word_tokens)*
+        }
+        /// the files with each extension
+        #visibility mod exts {
+            #(#ext_tokens)*
         }
+        #dir_words
     })
 }
 
-fn create_ts(foo: &Directory, file_to_index: &HashMap<PathBuf, usize>) -> proc_macro2::TokenStream {
-    let dir = foo
-        .name
-        .chars()
-        .map(|c| if c.is_alphanumeric() { c } else { '_' })
-        .collect::<String>();
-    let dir = format_ident!("_{}", dir);
-    let mut file_indices = foo
-        .files
-        .iter()
-        .map(|file| file_to_index[&file.path])
-        .collect::<Vec<_>>();
+fn create_ts(
+    directory: &Directory,
+    dir: &Ident,
+    root: usize,
+    visibility: &Visibility,
+    generate: &Generate,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let mut file_indices = Vec::new();
+    // the files by how many directories they are below this one
+    let mut depths = Vec::<Vec<usize>>::new();
+    for file in &directory.files {
+        let file_index = generate.file_to_index[&file.path];
+        let depth = file
+            .path
+            .strip_prefix(&directory.path)
+            .map_or(0, |relative| relative.components().count() - 1);
+        if depths.len() <= depth {
+            depths.resize_with(depth + 1, Vec::new);
+        }
+        depths[depth].push(file_index);
+        file_indices.push(file_index);
+    }
     file_indices.sort();
-    let file_indices = file_indices
+    let literals = |file_indices: Vec<usize>| {
+        file_indices
+            .into_iter()
+            .map(|file_index| TokenTree::Literal(Literal::usize_unsuffixed(file_index)))
+            .collect::<Vec<_>>()
+    };
+    let file_indices = literals(file_indices);
+    let depths = depths
         .into_iter()
-        .map(|file_index| TokenTree::Literal(Literal::usize_unsuffixed(file_index)));
-    let stem_word_tokens = create_const_arrays(&foo.stem_word_tokens, file_to_index);
-    let ext_tokens = create_const_arrays(&foo.ext_tokens, file_to_index);
-    let children = foo
+        .map(|mut file_indices| {
+            file_indices.sort();
+            literals(file_indices)
+        })
+        .collect::<Vec<_>>();
+    let direct_file_indices = depths.first().cloned().unwrap_or_default();
+    let token_modules = token_modules(
+        &directory.stem_word_tokens,
+        &directory.ext_tokens,
+        &directory.dir_word_tokens,
+        Some(root),
+        &parse_quote!(pub),
+        generate,
+    )?;
+    let child_idents = dir_idents(&directory.sub_dirs, root, generate)?;
+    let children = directory
         .sub_dirs
         .iter()
-        .map(|child| create_ts(child, file_to_index));
-    quote! {
-        pub mod #dir {
+        .zip(&child_idents)
+        .map(|(child, ident)| create_ts(child, ident, root, &parse_quote!(pub), generate))
+        .collect::<syn::Result<Vec<_>>>()?;
+    let doc = format!("the directory `{}`", directory.name);
+    Ok(quote! {
+        #[doc = #doc]
+        #visibility mod #dir {
+            /// the files in this directory and its sub directories
             pub const DIR: &[usize] = &[ #(#file_indices,)* ];
-            pub mod stem_words {
-                #(#stem_word_tokens)*
-            }
-            pub mod exts {
-                #(#ext_tokens)*
-            }
+            /// the files directly in this directory
+            pub const DIR_DIRECT: &[usize] = &[ #(#direct_file_indices,)* ];
+            /// the files in this directory and its sub directories by depth, `DIR_DEPTHS[0]` is
+            /// `DIR_DIRECT`, `DIR_DEPTHS[1]` the files directly in its sub directories, ...
+            pub const DIR_DEPTHS: &[&[usize]] = &[ #(&[ #(#depths,)* ],)* ];
+            #token_modules
             #(#children)*
         }
-    }
+    })
 }
 
-fn tokenize_dir_inner<P: AsRef<Path>>(
-    dir_paths: &Vec<P>,
-    delimiters: &Regex,
-) -> Result<proc_macro2::TokenStream> {
+fn tokenize_dir_inner(roots: &[Root], options: &Options) -> syn::Result<proc_macro2::TokenStream> {
     let mut files = Vec::new();
-    let mut directories = Vec::new();
-    for (i, dir_path) in dir_paths.iter().enumerate() {
-        let directory = tokenize_dir_inner_inner(dir_path, delimiters)?;
+    let mut directories = roots.iter().map(|_| None).collect::<Vec<_>>();
+    let mut warnings = Vec::new();
+    // overridden roots are walked last, so the files overriding theirs are known
+    let precedence = match options.overlay {
+        Some(overlay) => overlay.precedence(roots.len()),
+        None => (0..roots.len()).collect(),
+    };
+    let mut overridden = HashSet::new();
+    for i in precedence {
+        let root = &roots[i];
+        let mut walk = Walk {
+            root: &root.path,
+            options,
+            not_ignored: None,
+            overridden: &overridden,
+            ancestors: Vec::new(),
+            normalized: BTreeMap::new(),
+        };
+        let mut walk_root = || {
+            walk.not_ignored = options
+                .gitignore
+                .then(|| not_ignored(&root.path, &options.traversal))
+                .transpose()?;
+            tokenize_dir_inner_inner(&root.path, &mut walk)
+        };
+        let directory =
+            walk_root().map_err(|err| syn::Error::new(root.span, format!("{err:#}")))?;
+        for ((kind, normalized), tokens) in &walk.normalized {
+            if tokens.len() > 1 {
+                let tokens = tokens
+                    .iter()
+                    .map(|token| format!("`{token}`"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                warnings.push(warning(
+                    &format!("{tokens} are merged into the {kind} `{normalized}`"),
+                    root.span,
+                ));
+            }
+        }
         for file in &directory.files {
+            if let (Some(_), Ok(relative)) = (options.overlay, file.path.strip_prefix(&root.path)) {
+                overridden.insert(relative.to_owned());
+            }
             files.push((file.clone(), i));
         }
-        directories.push(directory);
+        directories[i] = Some(directory);
     }
-    files.sort_by(|(file1, _), (file2, _)| file1.path.cmp(&file2.path));
-    let num_files = files.len();
-    files.sort_by_key(|(file, dir_index)| {
-        file.negative_log_likelihood(
-            &directories[*dir_index].stem_word_tokens,
-            &directories[*dir_index].ext_tokens,
-            num_files,
-        )
+    let directories = directories.into_iter().flatten().collect::<Vec<_>>();
+    // ties in the likelihood keep this order, which only depends on the roots and the paths
+    // within them, not on where the roots are
+    files.sort_by(|(file1, dir_index1), (file2, dir_index2)| {
+        (dir_index1, &file1.path).cmp(&(dir_index2, &file2.path))
     });
+    let num_files = files.len();
+    match options.order {
+        Order::Likelihood => files.sort_by_key(|(file, dir_index)| {
+            file.negative_log_likelihood(
+                &directories[*dir_index].stem_word_tokens,
+                &directories[*dir_index].ext_tokens,
+                num_files,
+            )
+        }),
+        Order::Path => {}
+        Order::Natural => files.sort_by(|(file1, dir_index1), (file2, dir_index2)| {
+            dir_index1
+                .cmp(dir_index2)
+                .then_with(|| natural_cmp(&file1.path, &file2.path))
+        }),
+        Order::Modified | Order::Size => {
+            let mut keys = HashMap::new();
+            for (file, dir_index) in &files {
+                let key = options
+                    .order
+                    .metadata_key(&file.path)
+                    .map_err(|err| syn::Error::new(roots[*dir_index].span, format!("{err:#}")))?;
+                keys.insert(file.path.clone(), (*dir_index, key));
+            }
+            files.sort_by_key(|(file, _)| keys[&file.path]);
+        }
+    }
+    let mut indexed = Vec::new();
+    if let Some(lockfile) = &options.lockfile {
+        let keys = files
+            .iter()
+            .map(|(file, dir_index)| match options.overlay {
+                Some(_) => roots[*dir_index].overlay_key(&file.path),
+                None => roots[*dir_index].key(&file.path),
+            })
+            .collect::<syn::Result<Vec<_>>>()?;
+        let assignment = lockfile
+            .assign(&keys)
+            .map_err(|err| syn::Error::new(lockfile.span, format!("{err:#}")))?;
+        for &index in &assignment.removed {
+            if let Some(key) = &assignment.keys[index] {
+                warnings.push(warning(
+                    &format!(
+                        "`{key}` was removed, its index {index} stays reserved in the lockfile"
+                    ),
+                    lockfile.span,
+                ));
+            }
+        }
+        let mut by_key = keys.into_iter().zip(files).collect::<HashMap<_, _>>();
+        for key in &assignment.keys {
+            indexed.push(key.as_ref().and_then(|key| by_key.remove(key)));
+        }
+    } else {
+        indexed.extend(files.into_iter().map(Some));
+    }
     let mut files_to_index = HashMap::new();
-    for (index, (file, _dir_index)) in files.iter().enumerate() {
+    let mut file_lits = Vec::new();
+    let mut file_roots = Vec::new();
+    let mut tracked = Vec::new();
+    // the lockfile is written before this, so editing it reruns the macro
+    if let Some(lockfile) = &options.lockfile {
+        tracked.push(lockfile.path_expr.clone());
+    }
+    for (index, file) in indexed.into_iter().enumerate() {
+        let Some((file, dir_index)) = file else {
+            file_lits.push(quote! { "" });
+            file_roots.push(quote! { usize::MAX });
+            continue;
+        };
         files_to_index.insert(file.path.clone(), index);
+        let path = roots[dir_index].path_expr(&file.path)?;
+        file_lits.push(path.clone());
+        file_roots.push(TokenTree::Literal(Literal::usize_unsuffixed(dir_index)).into());
+        tracked.push(path);
     }
-    let mut file_lits = Vec::new();
-    for (file, _dir_index) in files {
-        file_lits.push(LitStr::new(
-            file.path.to_str().ok_or(anyhow!(""))?,
-            Span::call_site(),
-        ));
+    #[cfg(feature = "nightly")]
+    for directory in &directories {
+        track_dirs(directory);
     }
-    let foos = directories
+    let generate = Generate {
+        roots,
+        options,
+        file_to_index: &files_to_index,
+    };
+    let idents = root_idents(&generate)?;
+    let merged = if options.merged {
+        let mut merged: [BTreeMap<(String, usize), BTreeSet<PathBuf>>; 3] = Default::default();
+        for directory in &directories {
+            let tokens = [
+                &directory.stem_word_tokens,
+                &directory.ext_tokens,
+                &directory.dir_word_tokens,
+            ];
+            for (merged, tokens) in merged.iter_mut().zip(tokens) {
+                for (token, paths) in tokens {
+                    merged
+                        .entry(token.clone())
+                        .or_default()
+                        .extend(paths.iter().cloned());
+                }
+            }
+        }
+        let [stem_word_tokens, ext_tokens, dir_word_tokens] = &merged;
+        Some(token_modules(
+            stem_word_tokens,
+            ext_tokens,
+            dir_word_tokens,
+            None,
+            &options.visibility,
+            &generate,
+        )?)
+    } else {
+        None
+    };
+    let directories = directories
         .iter()
-        .map(|foo| create_ts(foo, &files_to_index));
+        .zip(&idents)
+        .enumerate()
+        .map(|(root, (directory, ident))| {
+            create_ts(directory, ident, root, &options.visibility, &generate)
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+    let visibility = &options.visibility;
+    let file_roots = options.overlay.map(|_| {
+        quote! {
+            /// the index of the root every file comes from, in the order the roots are given,
+            /// `usize::MAX` for indices a lockfile reserves for removed files
+            #visibility const FILE_ROOTS: &[usize] = &[ #(#file_roots,)* ];
+        }
+    });
     Ok(quote! {
-        pub const FILE_PATHS: &[&str] = &[ #(#file_lits,)* ];
-        #(#foos)*
+        /// the path of every file, indexed by the values in the generated constants, indices
+        /// a lockfile reserves for removed files have an empty path
+        #visibility const FILE_PATHS: &[&str] = &[ #(#file_lits,)* ];
+        #file_roots
+        // makes the compiler track every file, so editing, removing or renaming one
+        // reruns the macro
+        const _: &[&[u8]] = &[ #(include_bytes!(#tracked),)* ];
+        #(#warnings)*
+        #merged
+        #(#directories)*
     })
 }
+
+/// makes the compiler track `directory` and its sub directories, so adding a file to any
+/// of them reruns the macro
+#[cfg(feature = "nightly")]
+fn track_dirs(directory: &Directory) {
+    proc_macro::tracked::path(&directory.path);
+    for sub_dir in &directory.sub_dirs {
+        track_dirs(sub_dir);
+    }
+}
+
+/// a warning at `span`, stable proc macros cannot emit warnings, so this uses a deprecated item
+fn warning(message: &str, span: Span) -> proc_macro2::TokenStream {
+    let item = format_ident!("tokenize_dir", span = span);
+    quote_spanned! {span=>
+        const _: () = {
+            #[deprecated(note = #message)]
+            #[allow(non_upper_case_globals)]
+            const #item: () = ();
+            #item
+        };
+    }
+}
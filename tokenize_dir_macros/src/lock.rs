@@ -0,0 +1,283 @@
+use crate::root::Root;
+use anyhow::{Context, Result, anyhow};
+use proc_macro2::{Span, TokenStream};
+use std::{collections::HashSet, fs, io::ErrorKind, path::PathBuf};
+use syn::LitStr;
+
+const HEADER: &str = "\
+# the index tokenize_dir! assigned to every file, new files get the first free index
+# the lines of removed files are kept to reserve their index, delete them to free it
+";
+
+/// the file recording which index every file was assigned, so indices stay the same when
+/// files are added or removed
+pub struct Lockfile {
+    path: PathBuf,
+    /// an expression for `path`, like `Root::path_expr`
+    pub path_expr: TokenStream,
+    pub span: Span,
+}
+
+/// the indices assigned by a lockfile
+#[derive(Debug)]
+pub struct Assignment {
+    /// the key at every index, `None` for indices that are free
+    pub keys: Vec<Option<String>>,
+    /// the indices of keys in the lockfile that no longer exist
+    pub removed: Vec<usize>,
+}
+
+impl Lockfile {
+    /// resolved like the roots
+    pub fn parse(lit: &LitStr) -> syn::Result<Self> {
+        let root = Root::parse(lit)?;
+        Ok(Self {
+            path_expr: root.path_expr(&root.path)?,
+            path: root.path,
+            span: root.span,
+        })
+    }
+
+    /// the keys at every index in the lockfile, or nothing when it does not exist yet, indices
+    /// are below the number of lines and `num_files`, any other index was not assigned by the
+    /// macro
+    fn read(&self, num_files: usize) -> Result<Vec<Option<String>>> {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("failed to read `{}`", self.path.display()));
+            }
+        };
+        let mut keys = Vec::new();
+        let mut read = HashSet::new();
+        let max_index = contents.lines().count() + num_files;
+        for (line_number, line) in contents.lines().enumerate() {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = || {
+                anyhow!(
+                    "`{}` line {} is not `index path`",
+                    self.path.display(),
+                    line_number + 1
+                )
+            };
+            let (index, key) = line.split_once(' ').ok_or_else(invalid)?;
+            let index = index.parse::<usize>().map_err(|_| invalid())?;
+            if index >= max_index {
+                return Err(anyhow!(
+                    "`{}` line {} has the index {index}, which is too large, indices are below {max_index}",
+                    self.path.display(),
+                    line_number + 1
+                ));
+            }
+            if keys.len() <= index {
+                keys.resize(index + 1, None);
+            }
+            if keys[index].is_some() {
+                return Err(anyhow!(
+                    "`{}` assigns index {index} more than once",
+                    self.path.display()
+                ));
+            }
+            if !read.insert(key) {
+                return Err(anyhow!(
+                    "`{}` assigns `{key}` more than one index",
+                    self.path.display()
+                ));
+            }
+            keys[index] = Some(key.to_owned());
+        }
+        Ok(keys)
+    }
+
+    /// assigns an index to every key in `keys`, which are in the order new files should get
+    /// indices in, and updates the lockfile
+    pub fn assign(&self, keys: &[String]) -> Result<Assignment> {
+        let mut current = HashSet::new();
+        for key in keys {
+            if !current.insert(key) {
+                return Err(anyhow!(
+                    "`{key}` is found more than once, so it cannot be locked to one index"
+                ));
+            }
+        }
+        let mut assigned = self.read(keys.len())?;
+        let mut removed = Vec::new();
+        for (index, key) in assigned.iter().enumerate() {
+            if let Some(key) = key
+                && !current.contains(key)
+            {
+                removed.push(index);
+            }
+        }
+        let locked = assigned.iter().flatten().cloned().collect::<HashSet<_>>();
+        let mut free = (0..assigned.len())
+            .filter(|&index| assigned[index].is_none())
+            .collect::<Vec<_>>()
+            .into_iter();
+        for key in keys {
+            if locked.contains(key) {
+                continue;
+            }
+            match free.next() {
+                Some(index) => assigned[index] = Some(key.clone()),
+                None => assigned.push(Some(key.clone())),
+            }
+        }
+        let mut contents = HEADER.to_owned();
+        for (index, key) in assigned.iter().enumerate() {
+            if let Some(key) = key {
+                contents.push_str(&format!("{index} {key}\n"));
+            }
+        }
+        if fs::read_to_string(&self.path).ok().as_ref() != Some(&contents) {
+            fs::write(&self.path, contents)
+                .with_context(|| format!("failed to write `{}`", self.path.display()))?;
+        }
+        Ok(Assignment {
+            keys: assigned,
+            removed,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, process};
+
+    /// a lockfile in a new directory, which is removed when it is dropped
+    struct TempLockfile(Lockfile);
+
+    impl TempLockfile {
+        fn new(name: &str) -> Self {
+            let dir = env::temp_dir().join(format!("tokenize_dir_lock_{}_{name}", process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            Self(Lockfile {
+                path: dir.join("assets.lock"),
+                path_expr: TokenStream::new(),
+                span: Span::call_site(),
+            })
+        }
+
+        fn assign(&self, keys: &[&str]) -> Result<Assignment> {
+            let keys = keys.iter().map(|key| key.to_string()).collect::<Vec<_>>();
+            self.0.assign(&keys)
+        }
+
+        /// the lines after the header
+        fn lines(&self) -> Vec<String> {
+            fs::read_to_string(&self.0.path)
+                .unwrap()
+                .strip_prefix(HEADER)
+                .unwrap()
+                .lines()
+                .map(str::to_owned)
+                .collect()
+        }
+
+        fn write_lines(&self, lines: &[&str]) {
+            fs::write(&self.0.path, format!("{HEADER}{}\n", lines.join("\n"))).unwrap();
+        }
+    }
+
+    impl Drop for TempLockfile {
+        fn drop(&mut self) {
+            if let Some(dir) = self.0.path.parent() {
+                let _ = fs::remove_dir_all(dir);
+            }
+        }
+    }
+
+    fn keys(assignment: &Assignment) -> Vec<Option<&str>> {
+        assignment.keys.iter().map(Option::as_deref).collect()
+    }
+
+    #[test]
+    fn first_write() {
+        let lockfile = TempLockfile::new("first_write");
+        let assignment = lockfile.assign(&["b.png", "a.png"]).unwrap();
+        assert_eq!(keys(&assignment), [Some("b.png"), Some("a.png")]);
+        assert!(assignment.removed.is_empty());
+        assert_eq!(lockfile.lines(), ["0 b.png", "1 a.png"]);
+    }
+
+    #[test]
+    fn removed_file_keeps_its_index() {
+        let lockfile = TempLockfile::new("removed");
+        lockfile.assign(&["a.png", "b.png", "c.png"]).unwrap();
+        let assignment = lockfile.assign(&["a.png", "c.png"]).unwrap();
+        assert_eq!(
+            keys(&assignment),
+            [Some("a.png"), Some("b.png"), Some("c.png")]
+        );
+        assert_eq!(assignment.removed, [1]);
+        assert_eq!(lockfile.lines(), ["0 a.png", "1 b.png", "2 c.png"]);
+    }
+
+    #[test]
+    fn new_files_fill_deleted_lines_first() {
+        let lockfile = TempLockfile::new("fill");
+        lockfile.write_lines(&["0 a.png", "2 c.png"]);
+        let assignment = lockfile
+            .assign(&["a.png", "c.png", "d.png", "e.png"])
+            .unwrap();
+        assert_eq!(
+            keys(&assignment),
+            [Some("a.png"), Some("d.png"), Some("c.png"), Some("e.png")]
+        );
+        assert!(assignment.removed.is_empty());
+        assert_eq!(
+            lockfile.lines(),
+            ["0 a.png", "1 d.png", "2 c.png", "3 e.png"]
+        );
+    }
+
+    #[test]
+    fn too_large_index() {
+        let lockfile = TempLockfile::new("too_large");
+        lockfile.write_lines(&["0 a.png", "18446744073709551615 b.png"]);
+        let err = lockfile.assign(&["a.png", "b.png"]).unwrap_err();
+        assert!(
+            err.to_string().ends_with(
+                "line 4 has the index 18446744073709551615, which is too large, indices are below 6"
+            ),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn duplicate_index() {
+        let lockfile = TempLockfile::new("duplicate_index");
+        lockfile.write_lines(&["0 a.png", "0 b.png"]);
+        let err = lockfile.assign(&["a.png", "b.png"]).unwrap_err();
+        assert!(
+            err.to_string().ends_with("assigns index 0 more than once"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn duplicate_key() {
+        let lockfile = TempLockfile::new("duplicate_key");
+        lockfile.write_lines(&["0 a.png", "1 a.png"]);
+        let err = lockfile.assign(&["a.png"]).unwrap_err();
+        assert!(
+            err.to_string()
+                .ends_with("assigns `a.png` more than one index"),
+            "{err}"
+        );
+
+        let lockfile = TempLockfile::new("duplicate_file");
+        let err = lockfile.assign(&["a.png", "a.png"]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "`a.png` is found more than once, so it cannot be locked to one index"
+        );
+        assert!(!lockfile.0.path.exists());
+    }
+}
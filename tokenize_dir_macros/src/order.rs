@@ -0,0 +1,176 @@
+use anyhow::{Context, Result};
+use std::{
+    cmp::Ordering,
+    fs,
+    path::{Component, Path},
+    time::UNIX_EPOCH,
+};
+use syn::{
+    Ident,
+    parse::{Parse, ParseStream},
+};
+
+/// how files are ordered, which decides their indices, files are grouped by root first except
+/// with `likelihood`, and ties are ordered by root and then by path
+#[derive(Clone, Copy, Default)]
+pub enum Order {
+    /// `likelihood`, files with more common tokens come first, whichever root they are in
+    #[default]
+    Likelihood,
+    /// `path`, alphabetical by path
+    Path,
+    /// `natural`, like `path`, but runs of digits are compared by their value, so `2.png` comes
+    /// before `10.png`
+    Natural,
+    /// `modified`, oldest first
+    Modified,
+    /// `size`, smallest first
+    Size,
+}
+
+impl Parse for Order {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let ident = input.parse::<Ident>()?;
+        match ident.to_string().as_str() {
+            "likelihood" => Ok(Self::Likelihood),
+            "path" => Ok(Self::Path),
+            "natural" => Ok(Self::Natural),
+            "modified" => Ok(Self::Modified),
+            "size" => Ok(Self::Size),
+            _ => Err(syn::Error::new(
+                ident.span(),
+                format!(
+                    "unknown order `{ident}`, expected `likelihood`, `path`, `natural`, `modified` or `size`"
+                ),
+            )),
+        }
+    }
+}
+
+impl Order {
+    /// what the file at `path` is sorted by for orders that depend on its metadata, and `0` for
+    /// other orders
+    pub fn metadata_key(self, path: &Path) -> Result<u128> {
+        let metadata = || {
+            fs::metadata(path)
+                .with_context(|| format!("failed to read the metadata of `{}`", path.display()))
+        };
+        Ok(match self {
+            Self::Modified => metadata()?
+                .modified()
+                .with_context(|| format!("failed to read when `{}` was modified", path.display()))?
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos(),
+            Self::Size => metadata()?.len().into(),
+            _ => 0,
+        })
+    }
+}
+
+/// compares paths component by component like `Path::cmp`, with runs of digits compared by
+/// their value, runs with the same value are ordered by their number of leading zeros
+pub fn natural_cmp(a: &Path, b: &Path) -> Ordering {
+    let mut a = a.components();
+    let mut b = b.components();
+    loop {
+        let (Some(c), Some(d)) = (a.next(), b.next()) else {
+            return a.next().is_some().cmp(&b.next().is_some());
+        };
+        let ordering = match (c, d) {
+            (Component::Normal(c), Component::Normal(d)) => {
+                natural_str_cmp(&c.to_string_lossy(), &d.to_string_lossy())
+            }
+            _ => c.cmp(&d),
+        };
+        if ordering.is_ne() {
+            return ordering;
+        }
+    }
+}
+
+/// compares the names of path components like `natural_cmp`
+fn natural_str_cmp(a: &str, b: &str) -> Ordering {
+    let mut a = a.chars().peekable();
+    let mut b = b.chars().peekable();
+    loop {
+        let (Some(&c), Some(&d)) = (a.peek(), b.peek()) else {
+            return a.peek().is_some().cmp(&b.peek().is_some());
+        };
+        if c.is_ascii_digit() && d.is_ascii_digit() {
+            let digits = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+                let mut digits = String::new();
+                while let Some(c) = chars.next_if(char::is_ascii_digit) {
+                    digits.push(c);
+                }
+                digits
+            };
+            let c = digits(&mut a);
+            let d = digits(&mut b);
+            let c_value = c.trim_start_matches('0');
+            let d_value = d.trim_start_matches('0');
+            let ordering = c_value
+                .len()
+                .cmp(&d_value.len())
+                .then_with(|| c_value.cmp(d_value))
+                .then_with(|| c.len().cmp(&d.len()));
+            if ordering.is_ne() {
+                return ordering;
+            }
+        } else {
+            if c != d {
+                return c.cmp(&d);
+            }
+            a.next();
+            b.next();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(paths: &[&str]) -> Vec<String> {
+        let mut paths = paths.iter().map(Path::new).collect::<Vec<_>>();
+        paths.sort_by(|a, b| natural_cmp(a, b));
+        paths
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn digits_by_value() {
+        assert_eq!(
+            sorted(&["10.png", "2.png", "1.png", "a.png"]),
+            ["1.png", "2.png", "10.png", "a.png"]
+        );
+        assert_eq!(
+            sorted(&["tree10b", "tree2b", "tree2a"]),
+            ["tree2a", "tree2b", "tree10b"]
+        );
+    }
+
+    #[test]
+    fn leading_zeros() {
+        assert_eq!(sorted(&["01", "1", "001", "2"]), ["1", "01", "001", "2"]);
+        assert_eq!(sorted(&["01b", "1a"]), ["1a", "01b"]);
+    }
+
+    #[test]
+    fn directories_like_path() {
+        let paths = ["a-b/x", "a/y", "a/b/z", "a.png", "b"];
+        let mut by_path = paths.map(Path::new);
+        by_path.sort();
+        assert_eq!(
+            sorted(&paths),
+            by_path.map(|path| path.display().to_string())
+        );
+        assert_eq!(sorted(&paths), ["a/b/z", "a/y", "a-b/x", "a.png", "b"]);
+        assert_eq!(
+            sorted(&["a10/x", "a2/y", "a2-b"]),
+            ["a2/y", "a2-b", "a10/x"]
+        );
+    }
+}
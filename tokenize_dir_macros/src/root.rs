@@ -0,0 +1,201 @@
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use std::{
+    env,
+    path::{Component, Path, PathBuf},
+};
+use syn::{
+    Ident, LitStr,
+    parse::{Parse, ParseStream},
+};
+
+/// environment variables a root can be made relative to with a `$VAR/` prefix
+const BASES: &[&str] = &["CARGO_MANIFEST_DIR", "OUT_DIR"];
+
+/// which file is kept when several roots have a file at the same path relative to them, the
+/// others are left out as if they did not exist
+#[derive(Clone, Copy)]
+pub enum Overlay {
+    /// `first`, earlier roots override later ones
+    First,
+    /// `last`, later roots override earlier ones, like mods over a base asset pack
+    Last,
+}
+
+impl Parse for Overlay {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let ident = input.parse::<Ident>()?;
+        match ident.to_string().as_str() {
+            "first" => Ok(Self::First),
+            "last" => Ok(Self::Last),
+            _ => Err(syn::Error::new(
+                ident.span(),
+                format!("unknown overlay `{ident}`, expected `first` or `last`"),
+            )),
+        }
+    }
+}
+
+impl Overlay {
+    /// the indices of `num_roots` roots, from the one that overrides every other
+    pub fn precedence(self, num_roots: usize) -> Vec<usize> {
+        match self {
+            Self::First => (0..num_roots).collect(),
+            Self::Last => (0..num_roots).rev().collect(),
+        }
+    }
+}
+
+/// a root directory passed to `tokenize_dir!`
+pub struct Root {
+    /// the environment variable the root is relative to, `None` for absolute roots
+    pub base: Option<&'static str>,
+    /// the value of `base` when the macro was expanded
+    pub base_dir: PathBuf,
+    /// the resolved path of the root, used for walking it
+    pub path: PathBuf,
+    /// the path as written, without the `$VAR/` prefix, which the name of the module of the
+    /// root is generated from so it does not depend on where the crate is
+    pub written: PathBuf,
+    /// the identifier of the module of the root, `None` when it is generated
+    pub alias: Option<Ident>,
+    pub span: Span,
+}
+
+impl Root {
+    /// relative roots are resolved against `CARGO_MANIFEST_DIR`, unless they start with
+    /// `$OUT_DIR/` (or `$CARGO_MANIFEST_DIR/`), absolute roots are used as they are
+    pub fn parse(lit: &LitStr) -> syn::Result<Self> {
+        let value = lit.value();
+        let span = lit.span();
+        let (base, relative) = match value.strip_prefix('$') {
+            Some(rest) => {
+                let (var, relative) = rest.split_once('/').unwrap_or((rest, ""));
+                let base = BASES.iter().find(|base| **base == var).ok_or_else(|| {
+                    syn::Error::new(
+                        span,
+                        format!(
+                            "unknown base `${var}`, expected one of {}",
+                            BASES
+                                .iter()
+                                .map(|base| format!("`${base}`"))
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        ),
+                    )
+                })?;
+                (Some(*base), PathBuf::from(relative))
+            }
+            None if Path::new(&value).is_absolute() => (None, PathBuf::from(&value)),
+            None => (Some(BASES[0]), PathBuf::from(&value)),
+        };
+        let Some(base) = base else {
+            return Ok(Self {
+                base: None,
+                base_dir: PathBuf::new(),
+                path: relative.clone(),
+                written: relative,
+                alias: None,
+                span,
+            });
+        };
+        let base_dir = env::var_os(base).map(PathBuf::from).ok_or_else(|| {
+            syn::Error::new(
+                span,
+                format!("`{base}` is not set, so `{value}` cannot be resolved"),
+            )
+        })?;
+        Ok(Self {
+            base: Some(base),
+            path: base_dir.join(&relative),
+            written: relative,
+            base_dir,
+            alias: None,
+            span,
+        })
+    }
+
+    /// `path` (a file under this root) relative to `base_dir`, with a `/` before every
+    /// component, or all of `path` for absolute roots
+    fn relative_str(&self, path: &Path) -> syn::Result<String> {
+        let not_utf8 = || {
+            syn::Error::new(
+                self.span,
+                format!("`{}` is not valid UTF-8", path.display()),
+            )
+        };
+        if self.base.is_none() {
+            return Ok(path.to_str().ok_or_else(not_utf8)?.to_owned());
+        }
+        let relative = path.strip_prefix(&self.base_dir).map_err(|_| {
+            syn::Error::new(
+                self.span,
+                format!(
+                    "`{}` is not inside `{}`",
+                    path.display(),
+                    self.base_dir.display()
+                ),
+            )
+        })?;
+        let mut suffix = String::new();
+        for component in relative.components() {
+            let component = match component {
+                Component::Normal(component) => component.to_str().ok_or_else(not_utf8)?,
+                Component::ParentDir => "..",
+                _ => continue,
+            };
+            suffix.push('/');
+            suffix.push_str(component);
+        }
+        Ok(suffix)
+    }
+
+    /// an expression for `path` (a file under this root) that does not depend on the
+    /// directory the crate is built from
+    pub fn path_expr(&self, path: &Path) -> syn::Result<TokenStream> {
+        let relative = self.relative_str(path)?;
+        Ok(match self.base {
+            Some(base) => quote! { concat!(env!(#base), #relative) },
+            None => {
+                let path = LitStr::new(&relative, Span::call_site());
+                quote! { #path }
+            }
+        })
+    }
+
+    /// `path` (a file under this root) as it is written in lockfiles, relative to
+    /// `CARGO_MANIFEST_DIR`, or prefixed with `$OUT_DIR`, or absolute
+    pub fn key(&self, path: &Path) -> syn::Result<String> {
+        let relative = self.relative_str(path)?;
+        Ok(match self.base {
+            Some(base) if base == BASES[0] => relative.trim_start_matches('/').to_owned(),
+            Some(base) => format!("${base}{relative}"),
+            None => relative,
+        })
+    }
+
+    /// `path` (a file under this root) relative to this root, as it is written in lockfiles of
+    /// overlaid roots, so a file keeps its index when another root overrides it
+    pub fn overlay_key(&self, path: &Path) -> syn::Result<String> {
+        let relative = path.strip_prefix(&self.path).map_err(|_| {
+            syn::Error::new(
+                self.span,
+                format!(
+                    "`{}` is not inside `{}`",
+                    path.display(),
+                    self.path.display()
+                ),
+            )
+        })?;
+        let mut components = Vec::new();
+        for component in relative.components() {
+            components.push(component.as_os_str().to_str().ok_or_else(|| {
+                syn::Error::new(
+                    self.span,
+                    format!("`{}` is not valid UTF-8", path.display()),
+                )
+            })?);
+        }
+        Ok(components.join("/"))
+    }
+}
@@ -0,0 +1,299 @@
+use proc_macro2::Span;
+use regex::Regex;
+use std::borrow::Cow;
+use syn::{
+    Ident, LitStr, parenthesized,
+    parse::{Parse, ParseStream},
+};
+
+/// the delimiters used when none are given
+const DEFAULT_DELIMITERS: &[&str] = &["_", "-", " "];
+
+/// a place between two chars of a stem that is split on, which a regex cannot find without
+/// lookaround
+#[derive(Clone, Copy, PartialEq)]
+enum Boundary {
+    /// between letters and digits, `tree03large` is `tree`, `03`, `large`
+    Digit,
+    /// between lowercase and uppercase letters, `HTTPServerLog` is `HTTP`, `Server`, `Log`
+    Case,
+}
+
+/// the predefined classes of delimiters, with what they split on
+const CLASSES: &[(&str, Result<&str, Boundary>)] = &[
+    ("whitespace", Ok(r"\s")),
+    ("punctuation", Ok(r"\p{P}")),
+    ("digit_boundaries", Err(Boundary::Digit)),
+    ("case_boundaries", Err(Boundary::Case)),
+];
+
+/// something file stems are split on
+pub enum Delimiter {
+    /// `"_"`, a literal string
+    Literal(LitStr),
+    /// `regex(r"[\s_]+")`, every match of a regex
+    Regex(LitStr),
+    /// `whitespace`, `punctuation`, `digit_boundaries` or `case_boundaries`, one of `CLASSES`
+    Class(Ident),
+}
+
+impl Parse for Delimiter {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(LitStr) {
+            return Ok(Self::Literal(input.parse()?));
+        }
+        let ident = input.parse::<Ident>()?;
+        if ident != "regex" {
+            return Ok(Self::Class(ident));
+        }
+        let content;
+        parenthesized!(content in input);
+        Ok(Self::Regex(content.parse()?))
+    }
+}
+
+/// how the case of words and extensions is normalized, tokens that are the same after
+/// normalizing are merged
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum Case {
+    /// `preserve`, tokens are case sensitive
+    #[default]
+    Preserve,
+    /// `lowercase`, tokens are lowercased
+    Lowercase,
+    /// `fold`, tokens are Unicode case folded, so `Straße` and `STRASSE` are merged
+    Fold,
+}
+
+impl Parse for Case {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let ident = input.parse::<Ident>()?;
+        match ident.to_string().as_str() {
+            "preserve" => Ok(Self::Preserve),
+            "lowercase" => Ok(Self::Lowercase),
+            "fold" => Ok(Self::Fold),
+            _ => Err(syn::Error::new(
+                ident.span(),
+                format!("unknown case `{ident}`, expected `preserve`, `lowercase` or `fold`"),
+            )),
+        }
+    }
+}
+
+impl Case {
+    pub fn normalize<'a>(self, token: &'a str) -> Cow<'a, str> {
+        match self {
+            Self::Preserve => Cow::Borrowed(token),
+            Self::Lowercase => Cow::Owned(token.to_lowercase()),
+            Self::Fold => Cow::Owned(caseless::default_case_fold_str(token)),
+        }
+    }
+}
+
+/// splits file stems into words
+pub struct Splitter {
+    /// `None` when stems are not split on anything
+    delimiters: Option<Regex>,
+    boundaries: Vec<Boundary>,
+}
+
+impl Splitter {
+    /// `None` uses the default delimiters, no delimiters leaves every stem as one word
+    pub fn new(delimiters: Option<&[Delimiter]>) -> syn::Result<Self> {
+        let mut regexes = Vec::new();
+        let mut boundaries = Vec::new();
+        match delimiters {
+            Some(delimiters) => {
+                for delimiter in delimiters {
+                    match delimiter {
+                        Delimiter::Literal(lit) => {
+                            let delimiter = lit.value();
+                            if delimiter.is_empty() {
+                                return Err(syn::Error::new(
+                                    lit.span(),
+                                    "delimiter must not be empty",
+                                ));
+                            }
+                            regexes.push(regex::escape(&delimiter));
+                        }
+                        Delimiter::Regex(lit) => {
+                            let regex = lit.value();
+                            let compiled = Regex::new(&regex)
+                                .map_err(|err| syn::Error::new(lit.span(), err))?;
+                            if compiled.is_match("") {
+                                return Err(syn::Error::new(
+                                    lit.span(),
+                                    "delimiter regex must not match the empty string",
+                                ));
+                            }
+                            regexes.push(format!("(?:{regex})"));
+                        }
+                        Delimiter::Class(ident) => {
+                            let class = CLASSES.iter().find(|(name, _)| ident == name);
+                            match class {
+                                Some((_, Ok(regex))) => regexes.push(regex.to_string()),
+                                Some((_, Err(boundary))) => {
+                                    if !boundaries.contains(boundary) {
+                                        boundaries.push(*boundary);
+                                    }
+                                }
+                                None => {
+                                    return Err(syn::Error::new(
+                                        ident.span(),
+                                        format!(
+                                            "unknown delimiter class `{ident}`, expected `regex(..)` or one of {}",
+                                            CLASSES
+                                                .iter()
+                                                .map(|(name, _)| format!("`{name}`"))
+                                                .collect::<Vec<_>>()
+                                                .join(", ")
+                                        ),
+                                    ));
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            None => {
+                regexes.extend(DEFAULT_DELIMITERS.iter().map(|d| regex::escape(d)));
+            }
+        }
+        let delimiters = if regexes.is_empty() {
+            None
+        } else {
+            Some(
+                Regex::new(&regexes.join("|"))
+                    .map_err(|err| syn::Error::new(Span::call_site(), err))?,
+            )
+        };
+        Ok(Self {
+            delimiters,
+            boundaries,
+        })
+    }
+
+    /// the non empty words of `stem`
+    pub fn split<'a>(&self, stem: &'a str) -> Vec<&'a str> {
+        let parts = match &self.delimiters {
+            Some(delimiters) => delimiters.split(stem).collect(),
+            None => vec![stem],
+        };
+        let mut words = Vec::new();
+        for part in parts {
+            self.split_boundaries(part, &mut words);
+        }
+        words.retain(|word| !word.is_empty());
+        words
+    }
+
+    /// splits `part` at every boundary in `boundaries`
+    fn split_boundaries<'a>(&self, part: &'a str, words: &mut Vec<&'a str>) {
+        let chars = part.char_indices().collect::<Vec<_>>();
+        let mut start = 0;
+        for (j, &(i, next)) in chars.iter().enumerate().skip(1) {
+            let prev = chars[j - 1].1;
+            let after = chars.get(j + 1).map(|&(_, after)| after);
+            if self
+                .boundaries
+                .iter()
+                .any(|boundary| boundary.is_between(prev, next, after))
+            {
+                words.push(&part[start..i]);
+                start = i;
+            }
+        }
+        words.push(&part[start..]);
+    }
+}
+
+impl Boundary {
+    /// whether the boundary is between `prev` and `next`, where `after` follows `next`
+    fn is_between(self, prev: char, next: char, after: Option<char>) -> bool {
+        match self {
+            Self::Digit => {
+                (prev.is_alphabetic() && next.is_numeric())
+                    || (prev.is_numeric() && next.is_alphabetic())
+            }
+            Self::Case => {
+                ((prev.is_lowercase() || prev.is_numeric()) && next.is_uppercase())
+                    || (prev.is_uppercase()
+                        && next.is_uppercase()
+                        && after.is_some_and(char::is_lowercase))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::{Token, parse::Parser, punctuated::Punctuated};
+
+    /// a splitter for the delimiters as they are written in `delimiters = [...]`
+    fn splitter(delimiters: &str) -> syn::Result<Splitter> {
+        let delimiters =
+            Punctuated::<Delimiter, Token![,]>::parse_terminated.parse_str(delimiters)?;
+        Splitter::new(Some(&delimiters.into_iter().collect::<Vec<_>>()))
+    }
+
+    fn error(delimiters: &str) -> String {
+        match splitter(delimiters) {
+            Ok(_) => panic!("`{delimiters}` is accepted"),
+            Err(err) => err.to_string(),
+        }
+    }
+
+    #[test]
+    fn default_delimiters() {
+        let splitter = Splitter::new(None).unwrap();
+        assert_eq!(
+            splitter.split("big rock_02-mossy"),
+            ["big", "rock", "02", "mossy"]
+        );
+        assert_eq!(splitter.split("__a"), ["a"]);
+    }
+
+    #[test]
+    fn no_delimiters() {
+        assert_eq!(splitter("").unwrap().split("big rock_02"), ["big rock_02"]);
+    }
+
+    #[test]
+    fn digit_boundaries() {
+        let splitter = splitter("digit_boundaries").unwrap();
+        assert_eq!(splitter.split("tree03large"), ["tree", "03", "large"]);
+        assert_eq!(splitter.split("03"), ["03"]);
+    }
+
+    #[test]
+    fn case_boundaries() {
+        let case = splitter("case_boundaries").unwrap();
+        assert_eq!(case.split("PlayerIdleAnim"), ["Player", "Idle", "Anim"]);
+        assert_eq!(case.split("HTTPServer"), ["HTTP", "Server"]);
+        assert_eq!(case.split("Level2Boss"), ["Level2", "Boss"]);
+        assert_eq!(case.split("HTTP"), ["HTTP"]);
+        let both = splitter("case_boundaries, digit_boundaries").unwrap();
+        assert_eq!(both.split("Level2Boss"), ["Level", "2", "Boss"]);
+        assert_eq!(both.split("HTTPServer2Log"), ["HTTP", "Server", "2", "Log"]);
+    }
+
+    #[test]
+    fn regex_and_classes() {
+        let regex = splitter(r#"regex(r"[\s_\-]+"), ".""#).unwrap();
+        assert_eq!(regex.split("big -_rock.v2"), ["big", "rock", "v2"]);
+        let classes = splitter("whitespace, punctuation").unwrap();
+        assert_eq!(classes.split("big\trock,v2"), ["big", "rock", "v2"]);
+    }
+
+    #[test]
+    fn invalid_delimiters() {
+        assert_eq!(error(r#""""#), "delimiter must not be empty");
+        assert_eq!(
+            error(r#"regex("_*")"#),
+            "delimiter regex must not match the empty string"
+        );
+        assert!(error(r#"regex("(")"#).contains("unclosed group"));
+        assert!(error("letters").starts_with("unknown delimiter class `letters`"));
+    }
+}
@@ -0,0 +1,72 @@
+use anyhow::{Context, Result};
+use std::{fs, path::Path};
+
+/// how the directories under a root are walked
+pub struct Traversal {
+    /// how many directories below the root are entered, `Some(0)` only tokenizes the files
+    /// directly in the root, `None` enters every directory
+    pub max_depth: Option<usize>,
+    /// whether symlinks are followed, otherwise they are skipped
+    pub follow_links: bool,
+    /// whether files and directories starting with `.` are tokenized
+    pub hidden: bool,
+}
+
+impl Default for Traversal {
+    fn default() -> Self {
+        Self {
+            max_depth: None,
+            follow_links: true,
+            hidden: true,
+        }
+    }
+}
+
+impl Traversal {
+    /// whether to visit the entry at `path`, with `relative` to the root, before knowing what
+    /// it is
+    pub fn entry(&self, path: &Path, relative: &Path) -> Result<bool> {
+        if !self.hidden
+            && relative
+                .file_name()
+                .is_some_and(|name| name.as_encoded_bytes().starts_with(b"."))
+        {
+            return Ok(false);
+        }
+        if !self.follow_links {
+            let metadata = fs::symlink_metadata(path)
+                .with_context(|| format!("failed to read the metadata of `{}`", path.display()))?;
+            if metadata.file_type().is_symlink() {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// whether to descend into the directory at `relative` to the root
+    pub fn dir(&self, relative: &Path) -> bool {
+        self.max_depth
+            .is_none_or(|max_depth| relative.components().count() <= max_depth)
+    }
+}
+
+/// identifies a directory however it is reached, so symlinks to an ancestor are detected
+#[cfg(unix)]
+pub type DirId = (u64, u64);
+#[cfg(not(unix))]
+pub type DirId = std::path::PathBuf;
+
+/// the device and inode of the directory at `path`
+#[cfg(unix)]
+pub fn dir_id(path: &Path) -> Result<DirId> {
+    use std::os::unix::fs::MetadataExt;
+    let metadata = fs::metadata(path)
+        .with_context(|| format!("failed to read the metadata of `{}`", path.display()))?;
+    Ok((metadata.dev(), metadata.ino()))
+}
+
+/// the canonical path of the directory at `path`
+#[cfg(not(unix))]
+pub fn dir_id(path: &Path) -> Result<DirId> {
+    fs::canonicalize(path).with_context(|| format!("failed to resolve `{}`", path.display()))
+}
@@ -0,0 +1,81 @@
+use tokenize_dir_macros::tokenize_dir;
+
+mod assets {
+    super::tokenize_dir!("tests/fixtures/assets", order = path);
+}
+
+mod old_form {
+    super::tokenize_dir!("tests/fixtures/assets"; "-"; order = path);
+}
+
+mod options {
+    super::tokenize_dir!(
+        "tests/fixtures/assets" as assets,
+        order = path,
+        include = ["**/*.png"],
+        dir_words = true,
+        merged = true,
+        visibility = pub(crate),
+    );
+}
+
+/// the paths of `files` relative to this crate
+fn relative(files: &[&'static str]) -> Vec<&'static str> {
+    files
+        .iter()
+        .map(|file| file.strip_prefix(env!("CARGO_MANIFEST_DIR")).unwrap())
+        .collect()
+}
+
+#[test]
+fn files() {
+    assert_eq!(
+        relative(assets::FILE_PATHS),
+        [
+            "/tests/fixtures/assets/big rock.png",
+            "/tests/fixtures/assets/grass.png",
+            "/tests/fixtures/assets/tiles/grass-dark.png",
+            "/tests/fixtures/assets/tiles/stone.jpg",
+        ]
+    );
+    assert_eq!(assets::_assets::DIR, [0, 1, 2, 3]);
+    assert_eq!(assets::_assets::DIR_DIRECT, [0, 1]);
+    assert_eq!(assets::_assets::DIR_DEPTHS, [&[0, 1][..], &[2, 3]]);
+    assert_eq!(assets::_assets::_tiles::DIR, [2, 3]);
+}
+
+#[test]
+fn tokens() {
+    assert_eq!(assets::_assets::stem_words::_big, [0]);
+    assert_eq!(assets::_assets::stem_words::_rock, [0]);
+    assert_eq!(assets::_assets::stem_words::_grass, [1, 2]);
+    assert_eq!(assets::_assets::stem_words::_dark, [2]);
+    assert_eq!(assets::_assets::exts::_png, [0, 1, 2]);
+    assert_eq!(assets::_assets::exts::_jpg, [3]);
+    assert_eq!(assets::_assets::_tiles::stem_words::_grass, [2]);
+    assert_eq!(assets::_assets::_tiles::exts::_png, [2]);
+}
+
+#[test]
+fn old_form() {
+    assert_eq!(old_form::FILE_PATHS, assets::FILE_PATHS);
+    assert_eq!(old_form::_assets::stem_words::_big_rock, [0]);
+    assert_eq!(old_form::_assets::stem_words::_grass, [1, 2]);
+    assert_eq!(old_form::_assets::stem_words::_dark, [2]);
+}
+
+#[test]
+fn options() {
+    assert_eq!(
+        relative(options::FILE_PATHS),
+        [
+            "/tests/fixtures/assets/big rock.png",
+            "/tests/fixtures/assets/grass.png",
+            "/tests/fixtures/assets/tiles/grass-dark.png",
+        ]
+    );
+    assert_eq!(options::assets::dir_words::_tiles, [2]);
+    assert_eq!(options::stem_words::_grass, [1, 2]);
+    assert_eq!(options::exts::_png, [0, 1, 2]);
+    assert_eq!(options::dir_words::_tiles, [2]);
+}